@@ -0,0 +1,22 @@
+use anyhow::{bail, Result};
+use hive_capabilities::Schema;
+
+pub fn run(schema_path: &str) -> Result<()> {
+    let schema = Schema::from_toml_file(schema_path)?;
+    let diagnostics = schema.validate();
+
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+
+    let errors = diagnostics.iter().filter(|d| d.is_error()).count();
+    let warnings = diagnostics.len() - errors;
+
+    if errors > 0 {
+        bail!("Schema check failed: {} error(s), {} warning(s)", errors, warnings);
+    }
+
+    println!("Schema OK ({} warning(s))", warnings);
+
+    Ok(())
+}
@@ -1,10 +1,24 @@
-use anyhow::Result;
-use hive_capabilities::Schema;
+use anyhow::{bail, Result};
+use hive_capabilities::{CodegenTarget, ProjectConfig, Schema};
 
-pub async fn run(schema_path: &str, output: &str) -> Result<()> {
+pub async fn run(
+    schema_path: &str,
+    output: &str,
+    config_path: &str,
+    target: CodegenTarget,
+    check: bool,
+) -> Result<()> {
     let schema = Schema::from_toml_file(schema_path)?;
+    let config = ProjectConfig::load(config_path)?;
 
-    schema.write_models(output)?;
+    if check {
+        let differing = schema.check_models(target, output, &config.codegen)?;
+        if differing > 0 {
+            bail!("{} model file(s) are out of date; run `hive codegen`", differing);
+        }
+    } else {
+        schema.write_models(target, output, &config.codegen)?;
+    }
 
     Ok(())
 }
@@ -0,0 +1,57 @@
+use anyhow::{bail, Context, Result};
+use hive_capabilities::{format_toml, TomlSchema};
+use std::fs;
+
+pub fn run(schema_path: &str, check: bool) -> Result<()> {
+    let mut unformatted = 0;
+
+    for (file, single_table) in TomlSchema::source_files(schema_path)? {
+        let text = fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read {}", file.display()))?;
+        let formatted = format_toml(&text, single_table)
+            .with_context(|| format!("Failed to format {}", file.display()))?;
+
+        if formatted == text {
+            continue;
+        }
+
+        unformatted += 1;
+        if check {
+            println!("Would reformat {}", file.display());
+        } else {
+            fs::write(&file, formatted)?;
+            println!("Formatted {}", file.display());
+        }
+    }
+
+    if check && unformatted > 0 {
+        bail!("{} file(s) need formatting; run `hive fmt`", unformatted);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_leaves_files_alone() {
+        let dir = std::env::temp_dir().join(format!("hive-fmt-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("schema.toml");
+        let path = path.to_str().unwrap();
+        let unformatted = "[[table]]\ncolumns = { id = \"bigint\" }\nname = \"users\"\n";
+        fs::write(path, unformatted).unwrap();
+
+        let error = run(path, true).unwrap_err();
+        assert!(error.to_string().contains("1 file(s) need formatting"), "{}", error);
+        assert_eq!(fs::read_to_string(path).unwrap(), unformatted);
+
+        run(path, false).unwrap();
+        assert_ne!(fs::read_to_string(path).unwrap(), unformatted);
+        run(path, true).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
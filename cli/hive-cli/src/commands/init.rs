@@ -10,6 +10,9 @@ pub fn run(path: &str) -> anyhow::Result<()> {
     // Create schema file
     fs::write(schema_dir.join("schema.toml"), "")?;
 
+    // Create project config
+    fs::write(base.join("hive.toml"), DEFAULT_CONFIG)?;
+
     // Create .env file
     fs::write(base.join(".env"), "DATABASE_URL=\n")?;
 
@@ -17,4 +20,44 @@ pub fn run(path: &str) -> anyhow::Result<()> {
     fs::create_dir_all(base.join("models"))?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+const DEFAULT_CONFIG: &str = r#"[codegen]
+# Derives and attributes added to every generated type. A relation field
+# needs the same derives on the type it points at.
+derives = []
+attributes = []
+# Derive validator::Validate from varchar lengths and domain CHECKs
+validate = false
+# Newtypes such as `UserId` for primary keys and the foreign keys referencing them
+id_types = false
+
+# Rust types for codegen, keyed by Postgres type or `table.column`.
+# Write full paths, e.g. "time::OffsetDateTime" or "crate::types::Email".
+[codegen.types]
+
+# Struct names for tables whose singular form is not wanted, e.g. `people = "Person"`
+[codegen.structs]
+
+# Relation field names per foreign key column. A belongs_to is named after its
+# column without `_id`; a has_many after the child table, prefixed with that
+# name when the child has several foreign keys to the same table, as in
+# `author_posts` and `editor_posts`. Override them e.g.
+# "posts.author_id" = { belongs_to = "author", has_many = "authored_posts" }
+# "profiles.user_id" = { has_one = "profile" }
+# "post_tags.tag_id" = { many_to_many = "labels" }
+[codegen.relations]
+
+# `hive codegen --target typescript`: interfaces for the JSON of the Rust models
+[codegen.typescript]
+# Also emit a zod schema per type
+zod = false
+
+# `hive codegen --target openapi`: JSON Schemas for types it cannot map, e.g.
+# [codegen.openapi.types]
+# tsvector = { type = "string" }
+
+# Per-type derives, attributes and validate, replacing the values above, e.g.
+# [codegen.models.users]
+# derives = ["serde::Serialize", "serde::Deserialize", "PartialEq", "Eq", "Hash"]
+"#;
@@ -1,7 +1,8 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::Path;
 use hive_capabilities::{connect, Schema};
 
-pub async fn run(url: Option<&str>, output_path: String) -> Result<()> {
+pub async fn run(url: Option<&str>, output_path: String, split: bool) -> Result<()> {
 
     println!("Connecting to database...");
     let pool = connect(url).await?;
@@ -9,7 +10,19 @@ pub async fn run(url: Option<&str>, output_path: String) -> Result<()> {
     println!("Reading database schema...");
     let schema = Schema::from_db(&pool).await?;
 
-    schema.write_toml(&output_path)?;
+    if split {
+        // Accept either the schema directory or a file inside it
+        let path = Path::new(&output_path);
+        let dir = match path.extension() {
+            Some(ext) if ext == "toml" => path.parent().unwrap_or(Path::new(".")),
+            _ => path,
+        };
+        schema.write_toml_dir(&dir.to_string_lossy())?;
+    } else {
+        schema
+            .write_toml(&output_path)
+            .context("Pass --split to introspect into a split schema directory")?;
+    }
 
     Ok(())
 }
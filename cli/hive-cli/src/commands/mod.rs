@@ -1,4 +1,7 @@
 pub mod init;
 pub mod introspect;
 pub mod migrate;
-pub mod codegen;
\ No newline at end of file
+pub mod codegen;
+pub mod check;
+pub mod fmt;
+pub mod schema;
\ No newline at end of file
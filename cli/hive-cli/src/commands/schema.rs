@@ -0,0 +1,21 @@
+use anyhow::Result;
+use hive_capabilities::{TomlSchema, TomlTable};
+use std::fs;
+
+pub fn json_schema(output: Option<&str>, table: bool) -> Result<()> {
+    let json = if table {
+        TomlTable::json_schema()?
+    } else {
+        TomlSchema::json_schema()?
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, json + "\n")?;
+            println!("Wrote JSON Schema to {}", path);
+        }
+        None => println!("{}", json),
+    }
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 mod commands;
 
 use clap::{Parser, Subcommand};
+use hive_capabilities::CodegenTarget;
 
 #[derive(Parser)]
 #[command(name = "hive")]
@@ -20,7 +21,10 @@ enum Commands {
         #[arg(short, long)]
         db_url: Option<String>,
         #[arg(short, long, default_value = "./schema/schema.toml")]
-        output: String
+        output: String,
+        /// Write one file per table under `tables/` next to the output file
+        #[arg(long)]
+        split: bool
     },
     Migrate {
         #[arg(short, long)]
@@ -28,11 +32,47 @@ enum Commands {
         #[arg(short, long, default_value = "./schema/schema.toml")]
         schema_path: String
     },
+    Check {
+        #[arg(short, long, default_value = "./schema/schema.toml")]
+        schema_path: String
+    },
+    Fmt {
+        #[arg(short, long, default_value = "./schema/schema.toml")]
+        schema_path: String,
+        /// Fail instead of rewriting when a file is not formatted
+        #[arg(long)]
+        check: bool
+    },
     Codegen {
         #[arg(short, long, default_value = "./schema/schema.toml")]
         schema_path: String,
         #[arg(short, long, default_value = "./models")]
-        output: String
+        output: String,
+        #[arg(short, long, default_value = "./hive.toml")]
+        config: String,
+        /// What to generate: rust, typescript or openapi
+        #[arg(short, long, default_value = "rust")]
+        target: CodegenTarget,
+        /// Fail with a diff instead of writing when the models are out of date
+        #[arg(long)]
+        check: bool
+    },
+    Schema {
+        #[command(subcommand)]
+        command: SchemaCommands
+    }
+}
+
+#[derive(Subcommand)]
+enum SchemaCommands {
+    /// Print a JSON Schema for schema files, for editor completion
+    JsonSchema {
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Describe a split schema's `tables/*.toml` files instead
+        #[arg(long)]
+        table: bool
     }
 }
 
@@ -42,15 +82,26 @@ async fn main() -> anyhow::Result<()> {
 
     match cli.command {
         Commands::Init { path } => { commands::init::run(&path)?; }
-        Commands::Introspect { db_url, output } => {
-            commands::introspect::run(db_url.as_deref(), output).await?;
+        Commands::Introspect { db_url, output, split } => {
+            commands::introspect::run(db_url.as_deref(), output, split).await?;
         }
         Commands::Migrate { db_url, schema_path } => {
             commands::migrate::run(db_url.as_deref(), &schema_path).await?;
         }
-        Commands::Codegen { schema_path, output } => {
-            commands::codegen::run(&schema_path, &output).await?;
+        Commands::Check { schema_path } => {
+            commands::check::run(&schema_path)?;
+        }
+        Commands::Fmt { schema_path, check } => {
+            commands::fmt::run(&schema_path, check)?;
+        }
+        Commands::Codegen { schema_path, output, config, target, check } => {
+            commands::codegen::run(&schema_path, &output, &config, target, check).await?;
         }
+        Commands::Schema { command } => match command {
+            SchemaCommands::JsonSchema { output, table } => {
+                commands::schema::json_schema(output.as_deref(), table)?;
+            }
+        },
     }
     Ok(())
 }
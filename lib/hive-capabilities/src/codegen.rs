@@ -0,0 +1,2013 @@
+//! Model generation. Rust items are built as tokens with `quote`, parsed
+//! with `syn` and printed with `prettyplease`, so every generated file is
+//! valid, consistently formatted Rust. Other targets live in submodules.
+
+mod openapi;
+mod typescript;
+
+use anyhow::{bail, Context, Result};
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::{format_ident, quote};
+use similar::TextDiff;
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsStr;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use syn::parse::Parser;
+use syn::punctuated::Punctuated;
+
+use crate::config::CodegenConfig;
+use crate::inflect::{pluralize, singularize};
+use crate::schema::{CompositeType, Domain, ForeignKey, Schema};
+use crate::toml_schema::{TomlSchema, TomlTable, TomlView};
+
+// ============ Model Files ============
+
+/// What `hive codegen` writes models as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodegenTarget {
+    Rust,
+    TypeScript,
+    /// OpenAPI 3.1 component schemas
+    OpenApi,
+}
+
+impl CodegenTarget {
+    /// Extension of the files this target generates
+    fn extension(self) -> &'static str {
+        match self {
+            CodegenTarget::Rust => "rs",
+            CodegenTarget::TypeScript => "ts",
+            CodegenTarget::OpenApi => "json",
+        }
+    }
+}
+
+impl FromStr for CodegenTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "rust" => Ok(CodegenTarget::Rust),
+            "typescript" => Ok(CodegenTarget::TypeScript),
+            "openapi" => Ok(CodegenTarget::OpenApi),
+            _ => bail!("Unknown codegen target `{}`; expected rust, typescript or openapi", s),
+        }
+    }
+}
+
+impl Schema {
+    /// Generate model files from this schema, deleting generated files whose
+    /// table or type no longer exists
+    pub fn write_models(&self, target: CodegenTarget, output_path: &str, config: &CodegenConfig) -> Result<()> {
+        // Ensure output directory exists
+        fs::create_dir_all(output_path)?;
+
+        let files = self.render_models(target, output_path, config)?;
+        let orphans = orphaned_model_files(output_path, target.extension(), &files)?;
+
+        // Never delete hand-written code along with an orphaned file
+        for orphan in &orphans {
+            let existing = fs::read_to_string(orphan)?;
+            let custom = custom_code(&existing).with_context(|| format!("Failed to read {}", orphan.display()))?;
+            if !custom.trim().is_empty() {
+                bail!(
+                    "{} has custom code but its table or type no longer exists; move the code and delete the file",
+                    orphan.display()
+                );
+            }
+        }
+
+        for (path, content) in &files {
+            fs::write(path, content)?;
+        }
+        for orphan in &orphans {
+            fs::remove_file(orphan)?;
+            println!("Removed {}", orphan.display());
+        }
+
+        let models = self.tables.len() + self.views.len() + self.domains.len() + self.composite_types.len();
+        println!("Generated {} models in {}", models, output_path);
+
+        Ok(())
+    }
+
+    /// Compare the model files this schema generates with those on disk,
+    /// printing a unified diff for each stale, missing or orphaned file.
+    /// Returns the number of files that differ.
+    pub fn check_models(&self, target: CodegenTarget, output_path: &str, config: &CodegenConfig) -> Result<usize> {
+        let files = self.render_models(target, output_path, config)?;
+        let mut differing = 0;
+
+        for (path, content) in &files {
+            let name = path.display().to_string();
+            let (existing, old_name) = match fs::read_to_string(path) {
+                Ok(existing) => (existing, name.clone()),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => (String::new(), "/dev/null".to_string()),
+                Err(e) => return Err(e).with_context(|| format!("Failed to read {}", name)),
+            };
+            if existing != *content {
+                differing += 1;
+                print!("{}", unified_diff(&existing, content, &old_name, &name));
+            }
+        }
+
+        for orphan in orphaned_model_files(output_path, target.extension(), &files)? {
+            let existing = fs::read_to_string(&orphan)?;
+            differing += 1;
+            print!("{}", unified_diff(&existing, "", &orphan.display().to_string(), "/dev/null"));
+        }
+
+        Ok(differing)
+    }
+
+    /// Every model file of the target with its full content, keeping the
+    /// custom regions of the files already on disk
+    fn render_models(
+        &self,
+        target: CodegenTarget,
+        output_path: &str,
+        config: &CodegenConfig,
+    ) -> Result<Vec<(PathBuf, String)>> {
+        // Convert to TomlSchema for easier iteration (preserves table structure)
+        let toml_schema = TomlSchema::from_schema(self.clone());
+
+        let files = match target {
+            CodegenTarget::Rust => render_rust(&toml_schema, output_path, config)?,
+            CodegenTarget::TypeScript => typescript::render(&toml_schema, output_path, config)?,
+            // JSON has no comments to mark a custom region with
+            CodegenTarget::OpenApi => return openapi::render(&toml_schema, output_path, config),
+        };
+
+        files
+            .into_iter()
+            .map(|(path, generated)| with_custom_code(&path, generated).map(|content| (path, content)))
+            .collect()
+    }
+}
+
+/// Every Rust model file and mod.rs
+fn render_rust(schema: &TomlSchema, output_path: &str, config: &CodegenConfig) -> Result<Vec<(PathBuf, String)>> {
+    // Build relation maps
+    let relations = build_relations(schema, config)?;
+    let struct_names = build_struct_names(schema, config)?;
+
+    // Domains and composite types get their own Rust types
+    let user_types = user_type_names(schema);
+    let id_types = if config.id_types {
+        build_id_types(schema, &struct_names)
+    } else {
+        IdTypes::new()
+    };
+    let types = TypeMap {
+        user_types: user_types.clone(),
+        overrides: &config.types,
+        id_types,
+    };
+
+    let (models, check_warnings) = Models::new(schema, config, &types);
+
+    for warning in types.unknown_type_warnings(schema).into_iter().chain(check_warnings) {
+        println!("warning: {}", warning);
+    }
+
+    let mut files = Vec::new();
+
+    // Generate each table file
+    for table in &schema.table {
+        let file_content = generate_table_file(table, &relations, &struct_names, &types, &models)?;
+        let file_path = Path::new(output_path).join(format!("{}.rs", module_name(&table.name)));
+        files.push((file_path, file_content));
+    }
+
+    // Generate each view file
+    for view in &schema.view {
+        let file_content = generate_view_file(view, &struct_names[&view.name], &types, &models)?;
+        let file_path = Path::new(output_path).join(format!("{}.rs", module_name(&view.name)));
+        files.push((file_path, file_content));
+    }
+
+    // Generate each domain and composite type file
+    for domain in &schema.domain {
+        let file_content = generate_domain_file(domain, &types, &models)?;
+        let file_path = Path::new(output_path).join(format!("{}.rs", module_name(&domain.name)));
+        files.push((file_path, file_content));
+    }
+
+    for composite in &schema.composite_type {
+        let file_content = generate_composite_type_file(composite, &types, &models)?;
+        let file_path = Path::new(output_path).join(format!("{}.rs", module_name(&composite.name)));
+        files.push((file_path, file_content));
+    }
+
+    // Generate mod.rs
+    let mut modules: Vec<(&str, Vec<String>)> = struct_names
+        .iter()
+        .map(|(name, struct_name)| {
+            let mut exports = vec![struct_name.clone()];
+            exports.extend(types.own_id_type(name).map(|id_type| id_type.name.clone()));
+            (name.as_str(), exports)
+        })
+        .chain(user_types.iter().map(|name| (*name, vec![to_struct_name(name)])))
+        .collect();
+    modules.sort();
+
+    let mod_content = generate_mod_file(&modules)?;
+    let mod_path = Path::new(output_path).join("mod.rs");
+    files.push((mod_path, mod_content));
+
+    Ok(files)
+}
+
+// ============ Codegen Helpers ============
+
+/// Markers around hand-written code in a generated file
+const CUSTOM_BEGIN: &str = "// hive:begin custom";
+const CUSTOM_END: &str = "// hive:end";
+
+/// A generated file's content, ending in a custom region that keeps the
+/// hand-written code of the file it replaces
+fn with_custom_code(path: &Path, generated: String) -> Result<String> {
+    let custom = match fs::read_to_string(path) {
+        Ok(existing) => custom_code(&existing)
+            .with_context(|| format!("Refusing to overwrite {}", path.display()))?,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+    };
+
+    Ok(format!(
+        "{}\n// Code between these markers is kept when models are regenerated\n{}\n{}{}\n",
+        generated, CUSTOM_BEGIN, custom, CUSTOM_END
+    ))
+}
+
+/// Files in the output directory with a custom region, and so written by
+/// codegen, that are no longer generated
+fn orphaned_model_files(output_path: &str, extension: &str, files: &[(PathBuf, String)]) -> Result<Vec<PathBuf>> {
+    let entries = match fs::read_dir(output_path) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", output_path)),
+    };
+    let generated: HashSet<&OsStr> = files.iter().filter_map(|(path, _)| path.file_name()).collect();
+
+    let mut orphans = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let is_model_file = path.extension().is_some_and(|ext| ext == extension)
+            && path.file_name().is_some_and(|name| !generated.contains(name))
+            && fs::read_to_string(&path).is_ok_and(|text| text.lines().any(|line| line.trim() == CUSTOM_BEGIN));
+        if is_model_file {
+            orphans.push(path);
+        }
+    }
+    orphans.sort();
+
+    Ok(orphans)
+}
+
+fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    TextDiff::from_lines(old, new).unified_diff().header(old_name, new_name).to_string()
+}
+
+/// The lines inside every custom region of a file, in order
+fn custom_code(text: &str) -> Result<String> {
+    let mut code = String::new();
+    let mut inside = false;
+
+    for line in text.lines() {
+        match line.trim() {
+            CUSTOM_BEGIN if inside => bail!("`{}` inside a custom region", CUSTOM_BEGIN),
+            CUSTOM_BEGIN => inside = true,
+            CUSTOM_END if inside => inside = false,
+            _ if inside => {
+                code.push_str(line);
+                code.push('\n');
+            }
+            _ => {}
+        }
+    }
+
+    if inside {
+        bail!("`{}` has no matching `{}`", CUSTOM_BEGIN, CUSTOM_END);
+    }
+    Ok(code)
+}
+
+/// A relation field on a generated struct
+struct Relation {
+    /// Field name, before keyword escaping
+    field: String,
+    /// Table on the other side of the foreign key
+    table: String,
+    /// Foreign key column, on this table for a belongs_to and on the child
+    /// table for a has_many
+    column: String,
+}
+
+/// A has_one field for a child table whose foreign key column is unique
+struct HasOne {
+    /// Field name, before keyword escaping
+    field: String,
+    /// Child table holding the foreign key
+    table: String,
+    /// Foreign key column on the child table, and the column it references
+    column: String,
+    referenced_column: String,
+}
+
+/// A many-to-many field reached through a join table
+struct ManyToMany {
+    /// Field name, before keyword escaping
+    field: String,
+    /// Table on the far side of the join table
+    table: String,
+    join_table: String,
+    /// Join table column referencing this table, and the column it references
+    near_column: String,
+    near_referenced: String,
+    /// Join table column referencing the far table, and the column it references
+    far_column: String,
+    far_referenced: String,
+}
+
+/// table -> relation fields of that table's struct
+type RelationMap = HashMap<String, Vec<Relation>>;
+
+/// Relation fields of every table's struct
+#[derive(Default)]
+struct Relations {
+    belongs_to: RelationMap,
+    has_many: RelationMap,
+    has_one: HashMap<String, Vec<HasOne>>,
+    many_to_many: HashMap<String, Vec<ManyToMany>>,
+}
+
+/// table or view -> struct name
+type StructNames = HashMap<String, String>;
+
+/// A belongs_to is named after its column without `_id`; a has_many after
+/// the child table, prefixed with that name when the child has several
+/// foreign keys to the same parent; a many-to-many after the far column.
+/// A foreign key whose column is unique gives a singular has_one instead of
+/// a has_many. `[codegen.relations]` overrides any of them.
+fn build_relations(schema: &TomlSchema, config: &CodegenConfig) -> Result<Relations> {
+    let mut relations = Relations::default();
+    let table_names: HashSet<&str> = schema.table.iter().map(|t| t.name.as_str()).collect();
+
+    for table in &schema.table {
+        for fk in &table.foreign_key {
+            if !table_names.contains(fk.referenced_table.as_str()) {
+                continue;
+            }
+
+            let names = config.relations.get(&format!("{}.{}", table.name, fk.column));
+            let role = foreign_key_role(fk);
+            let siblings = table
+                .foreign_key
+                .iter()
+                .filter(|other| other.referenced_table == fk.referenced_table)
+                .count();
+            let child_name = |name: String| {
+                if siblings > 1 { format!("{}_{}", role, name) } else { name }
+            };
+
+            if is_unique_column(table, &fk.column) {
+                relations.has_one.entry(fk.referenced_table.clone()).or_default().push(HasOne {
+                    field: names
+                        .and_then(|n| n.has_one.clone())
+                        .unwrap_or_else(|| child_name(singularize(&table.name))),
+                    table: table.name.clone(),
+                    column: fk.column.clone(),
+                    referenced_column: fk.referenced_column.clone(),
+                });
+            } else {
+                relations.has_many.entry(fk.referenced_table.clone()).or_default().push(Relation {
+                    field: names
+                        .and_then(|n| n.has_many.clone())
+                        .unwrap_or_else(|| child_name(pluralize(&table.name))),
+                    table: table.name.clone(),
+                    column: fk.column.clone(),
+                });
+            }
+
+            relations.belongs_to.entry(table.name.clone()).or_default().push(Relation {
+                field: names.and_then(|n| n.belongs_to.clone()).unwrap_or(role),
+                table: fk.referenced_table.clone(),
+                column: fk.column.clone(),
+            });
+        }
+
+        if let Some((a, b)) = join_table_keys(table)
+            && table_names.contains(a.referenced_table.as_str())
+            && table_names.contains(b.referenced_table.as_str())
+        {
+            for (near, far) in [(a, b), (b, a)] {
+                let names = config.relations.get(&format!("{}.{}", table.name, far.column));
+                let field = names
+                    .and_then(|n| n.many_to_many.clone())
+                    .unwrap_or_else(|| pluralize(&foreign_key_role(far)));
+
+                relations.many_to_many.entry(near.referenced_table.clone()).or_default().push(ManyToMany {
+                    field,
+                    table: far.referenced_table.clone(),
+                    join_table: table.name.clone(),
+                    near_column: near.column.clone(),
+                    near_referenced: near.referenced_column.clone(),
+                    far_column: far.column.clone(),
+                    far_referenced: far.referenced_column.clone(),
+                });
+            }
+        }
+    }
+
+    check_relation_fields(schema, &relations)?;
+    Ok(relations)
+}
+
+/// Relation fields must not share a name with a column or with each other,
+/// or the struct would declare the field twice
+fn check_relation_fields(schema: &TomlSchema, relations: &Relations) -> Result<()> {
+    for table in &schema.table {
+        let key = |table: &str, column: &str| format!("\"{}.{}\"", table, column);
+        let columns = table.column.iter().map(|c| (c.name.clone(), format!("column `{}`", c.name)));
+        let belongs_to = relations.belongs_to.get(&table.name).into_iter().flatten().map(|r| {
+            (r.field.clone(), format!("belongs_to of {}", key(&table.name, &r.column)))
+        });
+        let has_one = relations
+            .has_one
+            .get(&table.name)
+            .into_iter()
+            .flatten()
+            .map(|r| (r.field.clone(), format!("has_one of {}", key(&r.table, &r.column))));
+        let has_many = relations
+            .has_many
+            .get(&table.name)
+            .into_iter()
+            .flatten()
+            .map(|r| (r.field.clone(), format!("has_many of {}", key(&r.table, &r.column))));
+        let many_to_many = relations.many_to_many.get(&table.name).into_iter().flatten().map(|r| {
+            (r.field.clone(), format!("many_to_many of {}", key(&r.join_table, &r.far_column)))
+        });
+
+        let mut fields: HashMap<String, String> = HashMap::new();
+        for (field, source) in columns.chain(belongs_to).chain(has_one).chain(has_many).chain(many_to_many) {
+            let name = field_name(&field);
+            if let Some(other) = fields.get(&name) {
+                bail!(
+                    "The `{}` model would have two `{}` fields, from the {} and the {}; rename the relation under [codegen.relations]",
+                    table.name, name, other, source
+                );
+            }
+            fields.insert(name, source);
+        }
+    }
+    Ok(())
+}
+
+/// What a foreign key column points at: `author` for `author_id`
+fn foreign_key_role(fk: &ForeignKey) -> String {
+    match fk.column.strip_suffix("_id") {
+        Some(role) if !role.is_empty() => role.to_string(),
+        _ => format!("{}_{}", fk.column, singularize(&fk.referenced_table)),
+    }
+}
+
+/// Whether a unique index or the primary key covers exactly this column
+fn is_unique_column(table: &TomlTable, column: &str) -> bool {
+    table
+        .index
+        .iter()
+        .any(|i| i.is_unique && matches!(i.columns.as_slice(), [c] if c == column))
+}
+
+/// The two foreign keys of a join table, whose primary key is exactly
+/// those two columns, as in `post_tags(post_id, tag_id)`
+fn join_table_keys(table: &TomlTable) -> Option<(&ForeignKey, &ForeignKey)> {
+    let pk = table.index.iter().find(|i| i.name.ends_with("_pkey"))?;
+    let [a, b] = pk.columns.as_slice() else {
+        return None;
+    };
+    let fk_for = |column: &String| table.foreign_key.iter().find(|fk| &fk.column == column);
+
+    Some((fk_for(a)?, fk_for(b)?))
+}
+
+/// Singular struct names for tables and views, unless `[codegen.structs]`
+/// names them. Names must be unique among these and the domain and
+/// composite types, since mod.rs re-exports them all.
+fn build_struct_names(schema: &TomlSchema, config: &CodegenConfig) -> Result<StructNames> {
+    let mut sources: HashMap<String, String> = HashMap::new();
+    let mut claim = |struct_name: String, source: String| -> Result<String> {
+        if let Some(other) = sources.get(&struct_name) {
+            bail!(
+                "Both {} and {} generate the struct `{}`; name one of them under [codegen.structs]",
+                other, source, struct_name
+            );
+        }
+        sources.insert(struct_name.clone(), source);
+        Ok(struct_name)
+    };
+
+    for domain in &schema.domain {
+        claim(to_struct_name(&domain.name), format!("domain `{}`", domain.name))?;
+    }
+    for composite in &schema.composite_type {
+        claim(to_struct_name(&composite.name), format!("composite type `{}`", composite.name))?;
+    }
+
+    let relations = schema
+        .table
+        .iter()
+        .map(|t| (&t.name, "table"))
+        .chain(schema.view.iter().map(|v| (&v.name, "view")));
+    let mut struct_names = StructNames::new();
+    for (name, kind) in relations {
+        let struct_name = match config.structs.get(name) {
+            Some(struct_name) => {
+                syn::parse_str::<Ident>(struct_name).with_context(|| {
+                    format!("[codegen.structs] name `{}` for `{}` is not a Rust identifier", struct_name, name)
+                })?;
+                struct_name.clone()
+            }
+            None => to_struct_name(&singularize(name)),
+        };
+        let struct_name = claim(struct_name, format!("{} `{}`", kind, name))?;
+        struct_names.insert(name.clone(), struct_name);
+    }
+
+    Ok(struct_names)
+}
+
+// ============ Generated Items ============
+
+fn generate_table_file(
+    table: &TomlTable,
+    relations: &Relations,
+    struct_names: &StructNames,
+    types: &TypeMap,
+    models: &Models,
+) -> Result<String> {
+    let struct_name = ident(&struct_names[&table.name]);
+    let validate = models.options(&table.name).validate;
+    let belongs_to = relations.belongs_to.get(&table.name).map(Vec::as_slice).unwrap_or_default();
+    let has_many = relations.has_many.get(&table.name).map(Vec::as_slice).unwrap_or_default();
+    let has_one = relations.has_one.get(&table.name).map(Vec::as_slice).unwrap_or_default();
+    let many_to_many = relations.many_to_many.get(&table.name).map(Vec::as_slice).unwrap_or_default();
+
+    // Collect relation imports
+    let mut relation_imports: Vec<&str> = belongs_to
+        .iter()
+        .chain(has_many)
+        .map(|r| r.table.as_str())
+        .chain(has_one.iter().map(|r| r.table.as_str()))
+        .chain(many_to_many.iter().map(|r| r.table.as_str()))
+        .filter(|t| *t != table.name)
+        .collect();
+    relation_imports.sort();
+    relation_imports.dedup();
+
+    let type_imports = types.imports(
+        table.column.iter().map(|c| (Some((table.name.as_str(), c.name.as_str())), c.data_type.as_str())),
+    );
+    let relation_imports = relation_imports.into_iter().map(|rel_table| {
+        let module = ident(&module_name(rel_table));
+        let name = ident(&struct_names[rel_table]);
+        quote! { use super::#module::#name; }
+    });
+
+    let id_type = match (types.own_id_type(&table.name), table.index.iter().find(|i| i.name.ends_with("_pkey"))) {
+        (Some(id_type), Some(pk)) => {
+            let inner = types.mapped_rust_type(Some((&table.name, &pk.columns[0])), &table_column_type(table, &pk.columns[0]));
+            Some(id_type_item(id_type, &table.name, &inner, types, models)?)
+        }
+        _ => None,
+    };
+
+    let docs = doc_attributes(table.comment.as_deref());
+    let attributes = models.type_attributes(&table.name, &["Debug", "Clone", "FromRow"], validate)?;
+
+    // Column fields
+    let mut fields = Vec::new();
+    for col in &table.column {
+        let column = Some((table.name.as_str(), col.name.as_str()));
+        let rust_type = parse_type(&types.rust_type(column, &col.data_type, col.is_nullable))?;
+        let rule = if validate { models.field_rule(types, column, &col.data_type) } else { None };
+        fields.push(field_tokens(&col.name, col.comment.as_deref(), rule, &rust_type, true));
+    }
+
+    // Relations are only set once loaded, so leave them out until then
+    let skip = if models.serializes(&table.name) {
+        quote! { #[sqlx(skip)] #[serde(skip_serializing_if = "Option::is_none")] }
+    } else {
+        quote! { #[sqlx(skip)] }
+    };
+    let relation_field = |doc: String, field: &str, rust_type: TokenStream| {
+        let field = ident(&field_name(field));
+        quote! { #[doc = #doc] #skip pub #field: #rust_type, }
+    };
+
+    for relation in belongs_to {
+        // A struct cannot hold itself without indirection
+        let parent = ident(&struct_names[&relation.table]);
+        let parent = if relation.table == table.name { quote!(Box<#parent>) } else { quote!(#parent) };
+        let doc = format!(" belongs_to `{}`", relation.table);
+        fields.push(relation_field(doc, &relation.field, quote!(Option<#parent>)));
+    }
+    for relation in has_one {
+        // Boxed since the child's belongs_to points back at this struct
+        let child = ident(&struct_names[&relation.table]);
+        let doc = format!(" has_one `{}`", relation.table);
+        fields.push(relation_field(doc, &relation.field, quote!(Option<Box<#child>>)));
+    }
+    for relation in has_many {
+        let child = ident(&struct_names[&relation.table]);
+        let doc = format!(" has_many `{}`", relation.table);
+        fields.push(relation_field(doc, &relation.field, quote!(Option<Vec<#child>>)));
+    }
+    for relation in many_to_many {
+        let other = ident(&struct_names[&relation.table]);
+        let doc = format!(" many_to_many `{}` through `{}`", relation.table, relation.join_table);
+        fields.push(relation_field(doc, &relation.field, quote!(Option<Vec<#other>>)));
+    }
+
+    let methods = has_one
+        .iter()
+        .map(|relation| has_one_method(relation, struct_names))
+        .chain(many_to_many.iter().map(|relation| many_to_many_methods(relation, struct_names)))
+        .collect::<Result<Vec<_>>>()?;
+    let methods = (!methods.is_empty()).then(|| quote! { impl #struct_name { #(#methods)* } });
+
+    let validate_import = validate_import(validate);
+
+    render_file(quote! {
+        use sqlx::FromRow;
+        #validate_import
+        #type_imports
+        #(#relation_imports)*
+
+        #id_type
+
+        #docs
+        #attributes
+        pub struct #struct_name {
+            #(#fields)*
+        }
+
+        #methods
+    })
+}
+
+/// `use validator::Validate;` for files deriving or implementing it, since
+/// the derived code calls `validate()` on `nested` fields as a method
+fn validate_import(validate: bool) -> Option<TokenStream> {
+    validate.then(|| quote! { use validator::Validate; })
+}
+
+fn table_column_type(table: &TomlTable, column: &str) -> String {
+    table
+        .column
+        .iter()
+        .find(|c| c.name == column)
+        .map(|c| c.data_type.clone())
+        .unwrap_or_default()
+}
+
+/// A transparent newtype over a primary key, with the serde derives of every model holding it
+fn id_type_item(id_type: &IdType, table: &str, inner: &str, types: &TypeMap, models: &Models) -> Result<TokenStream> {
+    let mut derives = vec!["Debug", "Clone"];
+    if ["i16", "i32", "i64", "Uuid"].contains(&inner) {
+        derives.push("Copy");
+    }
+    derives.extend(["PartialEq", "Eq", "Hash", "PartialOrd", "Ord", "sqlx::Type"]);
+
+    let mut serde_derives: Vec<&str> = Vec::new();
+    for user in types.id_type_users(id_type) {
+        for derive in models.options(user).derives {
+            let trait_name = derive.rsplit("::").next();
+            if matches!(trait_name, Some("Serialize" | "Deserialize"))
+                && !serde_derives.iter().any(|d| d.rsplit("::").next() == trait_name)
+            {
+                serde_derives.push(derive);
+            }
+        }
+    }
+    derives.extend(&serde_derives);
+
+    let derives = derives.into_iter().map(parse_path).collect::<Result<Vec<_>>>()?;
+    let serde = (!serde_derives.is_empty()).then(|| quote! { #[serde(transparent)] });
+    let doc = format!(" Primary key of `{}`", table);
+    let name = ident(&id_type.name);
+    let inner = parse_type(inner)?;
+
+    Ok(quote! {
+        #[doc = #doc]
+        #[derive(#(#derives),*)]
+        #[sqlx(transparent)]
+        #serde
+        pub struct #name(pub #inner);
+    })
+}
+
+/// `load_*` method for a has_one field
+fn has_one_method(relation: &HasOne, struct_names: &StructNames) -> Result<TokenStream> {
+    let escaped = field_name(&relation.field);
+    let unescaped = escaped.trim_start_matches("r#");
+    let field = ident(&escaped);
+    let load = format_ident!("load_{}", unescaped);
+    let child = ident(&struct_names[&relation.table]);
+    let key = ident(&field_name(&relation.referenced_column));
+    let select = sql_literal(&format!(
+        "SELECT * FROM {} WHERE {} = $1",
+        quote_identifier(&relation.table),
+        quote_identifier(&relation.column)
+    ))?;
+    let doc = format!(" Load `{}` by its `{}` column", unescaped, relation.column);
+
+    Ok(quote! {
+        #[doc = #doc]
+        pub async fn #load<'e>(&mut self, executor: impl sqlx::PgExecutor<'e>) -> Result<(), sqlx::Error> {
+            let row = sqlx::query_as::<_, #child>(#select)
+                .bind(&self.#key)
+                .fetch_optional(executor)
+                .await?;
+            self.#field = row.map(Box::new);
+            Ok(())
+        }
+    })
+}
+
+/// `load_*`, `link_*` and `unlink_*` methods for a many-to-many field
+fn many_to_many_methods(relation: &ManyToMany, struct_names: &StructNames) -> Result<TokenStream> {
+    let escaped = field_name(&relation.field);
+    let unescaped = escaped.trim_start_matches("r#");
+    let singular = singularize(unescaped);
+    let field = ident(&escaped);
+    let other = ident(&field_name(&singular));
+    let other_struct = ident(&struct_names[&relation.table]);
+    let self_key = ident(&field_name(&relation.near_referenced));
+    let other_key = ident(&field_name(&relation.far_referenced));
+    let load = format_ident!("load_{}", unescaped);
+    let link = format_ident!("link_{}", singular);
+    let unlink = format_ident!("unlink_{}", singular);
+
+    let join = quote_identifier(&relation.join_table);
+    let near = quote_identifier(&relation.near_column);
+    let far = quote_identifier(&relation.far_column);
+    let select = sql_literal(&format!(
+        "SELECT t.* FROM {} t JOIN {} j ON j.{} = t.{} WHERE j.{} = $1",
+        quote_identifier(&relation.table),
+        join,
+        far,
+        quote_identifier(&relation.far_referenced),
+        near
+    ))?;
+    let insert = sql_literal(&format!(
+        "INSERT INTO {} ({}, {}) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+        join, near, far
+    ))?;
+    let delete = sql_literal(&format!("DELETE FROM {} WHERE {} = $1 AND {} = $2", join, near, far))?;
+
+    let load_doc = format!(" Load `{}` through `{}`", unescaped, relation.join_table);
+    let link_doc = format!(" Add a row to `{}` linking this record to `{}`", relation.join_table, singular);
+    let unlink_doc = format!(" Remove the `{}` row linking this record to `{}`", relation.join_table, singular);
+
+    Ok(quote! {
+        #[doc = #load_doc]
+        pub async fn #load<'e>(&mut self, executor: impl sqlx::PgExecutor<'e>) -> Result<(), sqlx::Error> {
+            let rows = sqlx::query_as::<_, #other_struct>(#select)
+                .bind(&self.#self_key)
+                .fetch_all(executor)
+                .await?;
+            self.#field = Some(rows);
+            Ok(())
+        }
+
+        #[doc = #link_doc]
+        pub async fn #link<'e>(&self, executor: impl sqlx::PgExecutor<'e>, #other: &#other_struct) -> Result<(), sqlx::Error> {
+            sqlx::query(#insert)
+                .bind(&self.#self_key)
+                .bind(&#other.#other_key)
+                .execute(executor)
+                .await?;
+            Ok(())
+        }
+
+        #[doc = #unlink_doc]
+        pub async fn #unlink<'e>(&self, executor: impl sqlx::PgExecutor<'e>, #other: &#other_struct) -> Result<(), sqlx::Error> {
+            sqlx::query(#delete)
+                .bind(&self.#self_key)
+                .bind(&#other.#other_key)
+                .execute(executor)
+                .await?;
+            Ok(())
+        }
+    })
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// SQL as a raw string literal, with enough `#`s for its content
+fn sql_literal(sql: &str) -> Result<Literal> {
+    let mut hashes = "#".to_string();
+    while sql.contains(&format!("\"{}", hashes)) {
+        hashes.push('#');
+    }
+    let raw = format!("r{}\"{}\"{}", hashes, sql, hashes);
+    raw.parse().map_err(|_| anyhow::anyhow!("Failed to build a string literal for `{}`", sql))
+}
+
+/// Views are read-only, so they get a plain row struct with no relation fields
+fn generate_view_file(view: &TomlView, struct_name: &str, types: &TypeMap, models: &Models) -> Result<String> {
+    let imports = types.imports(
+        view.column.iter().map(|c| (Some((view.name.as_str(), c.name.as_str())), c.data_type.as_str())),
+    );
+
+    let kind = if view.materialized { "materialized view" } else { "view" };
+    let doc = format!(" Read-only row of the `{}` {}", view.name, kind);
+    let attributes = models.type_attributes(&view.name, &["Debug", "Clone", "FromRow"], false)?;
+    let name = ident(struct_name);
+
+    let mut fields = Vec::new();
+    for col in &view.column {
+        let rust_type = parse_type(&types.rust_type(Some((&view.name, &col.name)), &col.data_type, col.is_nullable))?;
+        fields.push(field_tokens(&col.name, col.comment.as_deref(), None, &rust_type, true));
+    }
+
+    render_file(quote! {
+        use sqlx::FromRow;
+        #imports
+
+        #[doc = #doc]
+        #attributes
+        pub struct #name {
+            #(#fields)*
+        }
+    })
+}
+
+/// Domains become transparent newtypes over their base type
+fn generate_domain_file(domain: &Domain, types: &TypeMap, models: &Models) -> Result<String> {
+    let imports = types.imports([(None, domain.base_type.as_str())]);
+    let doc = format!(" `{}` domain over {}", domain.name, domain.base_type);
+    let attributes = models.type_attributes(&domain.name, &["Debug", "Clone", "sqlx::Type"], false)?;
+    let name = ident(&to_struct_name(&domain.name));
+    let inner = parse_type(&types.rust_type(None, &domain.base_type, false))?;
+    let validate = models.domain_validate_impl(domain)?;
+    let validate_import = validate_import(validate.is_some());
+
+    render_file(quote! {
+        #validate_import
+        #imports
+
+        #[doc = #doc]
+        #attributes
+        #[sqlx(transparent)]
+        pub struct #name(pub #inner);
+
+        #validate
+    })
+}
+
+fn generate_composite_type_file(composite: &CompositeType, types: &TypeMap, models: &Models) -> Result<String> {
+    let imports = types.imports(
+        composite
+            .attribute
+            .iter()
+            .map(|a| (Some((composite.name.as_str(), a.name.as_str())), a.data_type.as_str())),
+    );
+
+    let doc = format!(" `{}` composite type", composite.name);
+    let validate = models.validated_composites.contains(&composite.name);
+    let attributes = models.type_attributes(&composite.name, &["Debug", "Clone", "sqlx::Type"], validate)?;
+    let type_name = &composite.name;
+    let name = ident(&to_struct_name(&composite.name));
+
+    let mut fields = Vec::new();
+    for attr in &composite.attribute {
+        let column = Some((composite.name.as_str(), attr.name.as_str()));
+        // Postgres can't enforce NOT NULL on composite attributes
+        let rust_type = parse_type(&types.rust_type(column, &attr.data_type, true))?;
+        let rule = if validate { models.field_rule(types, column, &attr.data_type) } else { None };
+        fields.push(field_tokens(&attr.name, None, rule, &rust_type, false));
+    }
+
+    let validate_import = validate_import(validate);
+
+    render_file(quote! {
+        #validate_import
+        #imports
+
+        #[doc = #doc]
+        #attributes
+        #[sqlx(type_name = #type_name)]
+        pub struct #name {
+            #(#fields)*
+        }
+    })
+}
+
+/// `modules` holds (type name, exported names) pairs
+fn generate_mod_file(modules: &[(&str, Vec<String>)]) -> Result<String> {
+    let declarations = modules.iter().map(|(name, _)| {
+        let module = ident(&module_name(name));
+        quote! { mod #module; }
+    });
+    let exports = modules.iter().map(|(name, exports)| {
+        let module = ident(&module_name(name));
+        let exports: Vec<Ident> = exports.iter().map(|export| ident(export)).collect();
+        match exports.as_slice() {
+            [export] => quote! { pub use #module::#export; },
+            _ => quote! { pub use #module::{#(#exports),*}; },
+        }
+    });
+
+    render_file(quote! {
+        #(#declarations)*
+        #(#exports)*
+    })
+}
+
+/// Rust field for a database column; a `#[sqlx(rename)]` keeps the mapping
+/// when the name had to change. sqlx strips `r#` on its own. Composite types
+/// are encoded by position and reject `rename`, so they pass `rename: false`.
+fn field_tokens(
+    column: &str,
+    comment: Option<&str>,
+    rule: Option<TokenStream>,
+    rust_type: &syn::Type,
+    rename: bool,
+) -> TokenStream {
+    let name = field_name(column);
+    let docs = doc_attributes(comment);
+    let rename = (rename && name.trim_start_matches("r#") != column).then(|| quote! { #[sqlx(rename = #column)] });
+    let field = ident(&name);
+
+    quote! {
+        #docs
+        #rule
+        #rename
+        pub #field: #rust_type,
+    }
+}
+
+/// A database comment as `///` lines
+fn doc_attributes(comment: Option<&str>) -> TokenStream {
+    let lines = comment.into_iter().flat_map(str::lines).map(|line| {
+        let line = line.trim_end();
+        if line.trim().is_empty() { String::new() } else { format!(" {}", line) }
+    });
+    quote! { #(#[doc = #lines])* }
+}
+
+/// Parse generated items and print them as formatted source, with a blank
+/// line between items other than runs of `use`s or `mod` declarations
+fn render_file(tokens: TokenStream) -> Result<String> {
+    let file: syn::File = syn::parse2(tokens).context("Generated code is not valid Rust")?;
+    let mut source = String::new();
+    let mut previous = None;
+
+    for item in file.items {
+        let group = match &item {
+            syn::Item::Use(_) => Some("use"),
+            syn::Item::Mod(module) if module.content.is_none() => Some("mod"),
+            _ => None,
+        };
+        if previous.is_some() && (group.is_none() || previous != Some(group)) {
+            source.push('\n');
+        }
+        previous = Some(group);
+        source.push_str(&prettyplease::unparse(&syn::File {
+            shebang: None,
+            attrs: Vec::new(),
+            items: vec![item],
+        }));
+    }
+
+    Ok(source)
+}
+
+fn parse_type(rust_type: &str) -> Result<syn::Type> {
+    syn::parse_str(rust_type).with_context(|| format!("`{}` is not a valid Rust type; check [codegen.types]", rust_type))
+}
+
+fn parse_path(path: &str) -> Result<syn::Path> {
+    syn::parse_str(path).with_context(|| format!("`{}` is not a valid derive path", path))
+}
+
+/// An identifier from a name already made valid by the naming helpers
+fn ident(name: &str) -> Ident {
+    match name.strip_prefix("r#") {
+        Some(raw) => Ident::new_raw(raw, Span::call_site()),
+        None => Ident::new(name, Span::call_site()),
+    }
+}
+
+/// Derives, attributes and validation rules for generated types
+struct Models<'a> {
+    config: &'a CodegenConfig,
+    /// Domains with something to validate -> Rust condition on `self.0`
+    domain_checks: HashMap<String, String>,
+    /// Composite types that derive `validator::Validate`
+    validated_composites: HashSet<String>,
+}
+
+/// Settings for one generated type after `[codegen.models]` overrides
+struct ModelOptions<'a> {
+    derives: &'a [String],
+    attributes: &'a [String],
+    validate: bool,
+}
+
+impl<'a> Models<'a> {
+    /// Also returns a warning for each domain CHECK too complex to validate
+    fn new(schema: &TomlSchema, config: &'a CodegenConfig, types: &TypeMap) -> (Self, Vec<String>) {
+        let mut models = Models {
+            config,
+            domain_checks: HashMap::new(),
+            validated_composites: HashSet::new(),
+        };
+        let mut warnings = Vec::new();
+
+        for domain in &schema.domain {
+            if !models.options(&domain.name).validate {
+                continue;
+            }
+            let rust_type = types.rust_type(None, &domain.base_type, false);
+            let mut conditions = Vec::new();
+            if let Some(max) = varchar_length(&domain.base_type) {
+                conditions.push(format!("self.0.chars().count() <= {}", max));
+            }
+            if let Some(check) = &domain.check {
+                match check_condition(check, &rust_type) {
+                    Some(condition) => conditions.push(condition),
+                    None => warnings.push(format!(
+                        "CHECK on domain `{}` is not a simple comparison and is not validated",
+                        domain.name
+                    )),
+                }
+            }
+            if !conditions.is_empty() {
+                models.domain_checks.insert(domain.name.clone(), conditions.join(" && "));
+            }
+        }
+
+        for composite in &schema.composite_type {
+            if models.options(&composite.name).validate {
+                models.validated_composites.insert(composite.name.clone());
+            }
+        }
+
+        (models, warnings)
+    }
+
+    fn options(&self, name: &str) -> ModelOptions<'a> {
+        model_options(self.config, name)
+    }
+
+    /// The `#[derive(...)]` and extra attributes for a generated type
+    fn type_attributes(&self, name: &str, base_derives: &[&str], validate: bool) -> Result<TokenStream> {
+        let options = self.options(name);
+        let mut derives: Vec<&str> = base_derives.to_vec();
+        for derive in options.derives {
+            if !derives.contains(&derive.as_str()) {
+                derives.push(derive);
+            }
+        }
+        if validate {
+            derives.push("Validate");
+        }
+
+        let derives = derives.into_iter().map(parse_path).collect::<Result<Vec<_>>>()?;
+        let mut attributes = Vec::new();
+        for attribute in options.attributes {
+            let parsed = syn::Attribute::parse_outer
+                .parse_str(attribute)
+                .with_context(|| format!("`{}` for `{}` is not a valid attribute", attribute, name))?;
+            attributes.extend(parsed);
+        }
+
+        Ok(quote! {
+            #[derive(#(#derives),*)]
+            #(#attributes)*
+        })
+    }
+
+    /// Whether the type derives serde's `Serialize`, so unloaded relations
+    /// can be left out of its output
+    fn serializes(&self, name: &str) -> bool {
+        self.options(name).derives.iter().any(|d| d.rsplit("::").next() == Some("Serialize"))
+    }
+
+    /// `#[validate(...)]` for a field: a length for varchar columns, or
+    /// nested for domains and composite types with rules of their own.
+    /// NOT NULL needs no rule since those fields are not `Option`s.
+    fn field_rule(&self, types: &TypeMap, column: ColumnKey, data_type: &str) -> Option<TokenStream> {
+        match types.map(column, data_type) {
+            MappedType::User(user_type)
+                if self.domain_checks.contains_key(user_type) || self.validated_composites.contains(user_type) =>
+            {
+                Some(quote! { #[validate(nested)] })
+            }
+            MappedType::Builtin("String") => {
+                varchar_length(data_type).map(|max| {
+                    let max = Literal::u32_unsuffixed(max);
+                    quote! { #[validate(length(max = #max))] }
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// `impl Validate` for a domain newtype, since its CHECK
+    /// cannot be written as a field attribute
+    fn domain_validate_impl(&self, domain: &Domain) -> Result<Option<TokenStream>> {
+        let Some(condition) = self.domain_checks.get(&domain.name) else {
+            return Ok(None);
+        };
+
+        let name = ident(&to_struct_name(&domain.name));
+        let condition: syn::Expr = syn::parse_str(condition)
+            .with_context(|| format!("Failed to build the CHECK condition for domain `{}`", domain.name))?;
+        let code = format!("{}_check", domain.name);
+
+        Ok(Some(quote! {
+            impl Validate for #name {
+                fn validate(&self) -> Result<(), validator::ValidationErrors> {
+                    let mut errors = validator::ValidationErrors::new();
+                    if !(#condition) {
+                        errors.add("0", validator::ValidationError::new(#code));
+                    }
+                    if errors.is_empty() { Ok(()) } else { Err(errors) }
+                }
+            }
+        }))
+    }
+}
+
+fn model_options<'a>(config: &'a CodegenConfig, name: &str) -> ModelOptions<'a> {
+    let model = config.models.get(name);
+    ModelOptions {
+        derives: model.and_then(|m| m.derives.as_deref()).unwrap_or(&config.derives),
+        attributes: model.and_then(|m| m.attributes.as_deref()).unwrap_or(&config.attributes),
+        validate: model.and_then(|m| m.validate).unwrap_or(config.validate),
+    }
+}
+
+impl ModelOptions<'_> {
+    /// The name serde gives a Rust field, following a `#[serde(rename_all)]`
+    /// among the type's attributes
+    fn serde_name(&self, field: &str) -> Result<String> {
+        let field = field.trim_start_matches("r#");
+        let mut rule = None;
+        for attribute in self.attributes {
+            let parsed = syn::Attribute::parse_outer
+                .parse_str(attribute)
+                .with_context(|| format!("`{}` is not a valid attribute", attribute))?;
+            for serde in parsed.iter().filter(|a| a.path().is_ident("serde")) {
+                let arguments = serde
+                    .parse_args_with(Punctuated::<syn::Meta, syn::Token![,]>::parse_terminated)
+                    .with_context(|| format!("`{}` is not a valid serde attribute", attribute))?;
+                for argument in arguments {
+                    if let syn::Meta::NameValue(name_value) = argument
+                        && name_value.path.is_ident("rename_all")
+                        && let syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(value), .. }) = name_value.value
+                    {
+                        rule = Some(value.value());
+                    }
+                }
+            }
+        }
+
+        let words = field.split('_').filter(|w| !w.is_empty());
+        let capitalized = || words.clone().map(|w| w[..1].to_uppercase() + &w[1..]).collect::<String>();
+        Ok(match rule.as_deref() {
+            Some("lowercase") | Some("snake_case") | None => field.to_string(),
+            Some("UPPERCASE") | Some("SCREAMING_SNAKE_CASE") => field.to_uppercase(),
+            Some("PascalCase") => capitalized(),
+            Some("camelCase") => {
+                let pascal = capitalized();
+                pascal[..1].to_lowercase() + &pascal[1..]
+            }
+            Some("kebab-case") => field.replace('_', "-"),
+            Some("SCREAMING-KEBAB-CASE") => field.replace('_', "-").to_uppercase(),
+            Some(other) => bail!("Unknown serde rename_all rule `{}`", other),
+        })
+    }
+}
+
+/// The strings a CHECK such as `VALUE IN ('a', 'b')` allows, also in the
+/// `VALUE = ANY (ARRAY['a'::text, 'b'::text])` form Postgres writes back
+fn enum_values(check: &str) -> Option<Vec<String>> {
+    let mut values = Vec::new();
+    let mut skeleton = String::new();
+    let mut chars = check.chars().peekable();
+
+    // Replace each string literal with `?`, keeping the literals' case
+    while let Some(c) = chars.next() {
+        if c != '\'' {
+            if !c.is_whitespace() {
+                skeleton.push(c.to_ascii_lowercase());
+            }
+            continue;
+        }
+        let mut value = String::new();
+        loop {
+            match chars.next()? {
+                '\'' if chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    value.push('\'');
+                }
+                '\'' => break,
+                c => value.push(c),
+            }
+        }
+        values.push(value);
+        skeleton.push('?');
+    }
+
+    for cast in ["::text[]", "::charactervarying[]", "::text", "::charactervarying"] {
+        skeleton = skeleton.replace(cast, "");
+    }
+    let skeleton = skeleton.replace("(value)", "value").replace("((array[", "(array[").replace("]))", "])");
+    let mut skeleton = skeleton.as_str();
+    while let Some(inner) = skeleton.strip_prefix('(').and_then(|s| s.strip_suffix(')')) {
+        skeleton = inner;
+    }
+
+    let list = skeleton
+        .strip_prefix("valuein(")
+        .and_then(|s| s.strip_suffix(')'))
+        .or_else(|| skeleton.strip_prefix("value=any(array[").and_then(|s| s.strip_suffix("])")))?;
+    let expected = vec!["?"; values.len()].join(",");
+    (!values.is_empty() && list == expected).then_some(values)
+}
+
+/// `n` for `varchar(n)`, `character varying(n)`, `char(n)` or `character(n)`
+fn varchar_length(data_type: &str) -> Option<u32> {
+    let lower = data_type.to_lowercase();
+    let (name, rest) = lower.split_once('(')?;
+    if !["varchar", "character varying", "char", "character"].contains(&name.trim()) {
+        return None;
+    }
+    rest.strip_suffix(')')?.trim().parse().ok()
+}
+
+/// A Rust condition on `self.0` for a CHECK made only of comparisons of
+/// `VALUE` or `length(VALUE)` against numbers, joined by AND. The casts and
+/// quoted constants Postgres writes when it deparses a CHECK are accepted.
+fn check_condition(check: &str, rust_type: &str) -> Option<String> {
+    let normalized = strip_casts(&check.to_lowercase()).replace(['(', ')'], " ");
+    let mut tokens: Vec<&str> = Vec::new();
+    for token in normalized.split_whitespace() {
+        // `'-1'::integer` is how Postgres writes a negative constant
+        let token = token
+            .strip_prefix('\'')
+            .and_then(|t| t.strip_suffix('\''))
+            .filter(|t| t.parse::<f64>().is_ok())
+            .unwrap_or(token);
+        match (tokens.last(), token) {
+            (Some(&("char_length" | "character_length" | "length")), "value") => {
+                tokens.pop();
+                tokens.push("length");
+            }
+            _ => tokens.push(token),
+        }
+    }
+
+    let operand = |token: &str| match token {
+        "value" if ["i16", "i32", "i64", "f32", "f64"].contains(&rust_type) => Some("self.0"),
+        "length" if rust_type == "String" => Some("self.0.chars().count()"),
+        _ => None,
+    };
+    let literal = |token: &str, operand: &str| -> Option<String> {
+        if operand == "self.0" && rust_type.starts_with('f') {
+            token.parse::<f64>().ok().map(|n| format!("{:?}", n))
+        } else if operand == "self.0" {
+            token.parse::<i64>().ok().map(|n| n.to_string())
+        } else {
+            token.parse::<usize>().ok().map(|n| n.to_string())
+        }
+    };
+    // With the number on the left, `0 < VALUE` becomes `self.0 > 0`
+    let operator = |token: &str, flipped: bool| -> Option<&'static str> {
+        Some(match (token, flipped) {
+            ("=", _) => "==",
+            ("<>" | "!=", _) => "!=",
+            ("<", false) | (">", true) => "<",
+            ("<=", false) | (">=", true) => "<=",
+            (">", false) | ("<", true) => ">",
+            (">=", false) | ("<=", true) => ">=",
+            _ => return None,
+        })
+    };
+
+    let mut conditions = Vec::new();
+    let mut rest = tokens.as_slice();
+    loop {
+        match rest {
+            [left, "between", low, "and", high, tail @ ..] => {
+                let left = operand(left)?;
+                conditions.push(format!(
+                    "{} >= {} && {} <= {}",
+                    left,
+                    literal(low, left)?,
+                    left,
+                    literal(high, left)?
+                ));
+                rest = tail;
+            }
+            [left, op, right, tail @ ..] => {
+                let condition = match (operand(left), operand(right)) {
+                    (Some(left), None) => format!("{} {} {}", left, operator(op, false)?, literal(right, left)?),
+                    (None, Some(right)) => format!("{} {} {}", right, operator(op, true)?, literal(left, right)?),
+                    _ => return None,
+                };
+                conditions.push(condition);
+                rest = tail;
+            }
+            _ => return None,
+        }
+
+        match rest {
+            [] => break,
+            ["and", tail @ ..] => rest = tail,
+            _ => return None,
+        }
+    }
+
+    Some(conditions.join(" && "))
+}
+
+/// Drop `::type` casts, such as the `(VALUE)::text` Postgres writes for
+/// `char_length(VALUE)` on a varchar domain
+fn strip_casts(sql: &str) -> String {
+    let mut stripped = String::with_capacity(sql.len());
+    let mut rest = sql;
+    while let Some(start) = rest.find("::") {
+        stripped.push_str(&rest[..start]);
+        rest = &rest[start + 2..];
+
+        let end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let name = &rest[..end];
+        rest = &rest[end..];
+        for (first, second) in [("double", " precision"), ("character", " varying")] {
+            if name == first && rest.starts_with(second) {
+                rest = &rest[second.len()..];
+            }
+        }
+        if rest.starts_with('(')
+            && let Some(close) = rest.find(')')
+        {
+            rest = &rest[close + 1..];
+        }
+        while let Some(tail) = rest.strip_prefix("[]") {
+            rest = tail;
+        }
+    }
+    stripped.push_str(rest);
+    stripped
+}
+
+/// Maps Postgres types to Rust types: `[codegen.types]` overrides first,
+/// then generated domain/composite types, then the built-in mapping.
+/// Primary and foreign key columns with an id newtype use it instead,
+/// unless overridden by `table.column`.
+struct TypeMap<'a> {
+    user_types: HashSet<&'a str>,
+    overrides: &'a HashMap<String, String>,
+    id_types: IdTypes,
+}
+
+/// A newtype such as `UserId` for a table's single-column primary key
+#[derive(Clone)]
+struct IdType {
+    /// Table whose file defines the newtype
+    table: String,
+    name: String,
+}
+
+/// `(table, column)` -> id newtype, for primary keys and the foreign keys
+/// referencing them
+type IdTypes = HashMap<(String, String), IdType>;
+
+fn build_id_types(schema: &TomlSchema, struct_names: &StructNames) -> IdTypes {
+    let mut id_types = IdTypes::new();
+    for table in &schema.table {
+        for column in &table.column {
+            if let Some(id_type) = resolve_id_type(schema, struct_names, &table.name, &column.name, 0) {
+                id_types.insert((table.name.clone(), column.name.clone()), id_type);
+            }
+        }
+    }
+    id_types
+}
+
+/// Follows foreign keys to the primary key they end at, so a key that is
+/// also a foreign key shares the referenced table's newtype
+fn resolve_id_type(
+    schema: &TomlSchema,
+    struct_names: &StructNames,
+    table_name: &str,
+    column: &str,
+    depth: usize,
+) -> Option<IdType> {
+    let table = schema.table.iter().find(|t| t.name == table_name)?;
+
+    if depth <= schema.table.len()
+        && let Some(fk) = table.foreign_key.iter().find(|fk| fk.column == column)
+        && let Some(id_type) =
+            resolve_id_type(schema, struct_names, &fk.referenced_table, &fk.referenced_column, depth + 1)
+    {
+        return Some(id_type);
+    }
+
+    let pk = table.index.iter().find(|i| i.name.ends_with("_pkey"))?;
+    matches!(pk.columns.as_slice(), [c] if c == column).then(|| IdType {
+        table: table.name.clone(),
+        name: format!("{}Id", struct_names[&table.name]),
+    })
+}
+
+/// Where a column's Rust type came from
+enum MappedType<'a> {
+    Override(&'a str),
+    User(&'a str),
+    Builtin(&'static str),
+    Unknown,
+}
+
+/// `(owner, column)` for a column of a table, view or composite type
+type ColumnKey<'a> = Option<(&'a str, &'a str)>;
+
+impl<'a> TypeMap<'a> {
+    fn map<'s>(&'s self, column: ColumnKey, data_type: &'s str) -> MappedType<'s> {
+        if let Some((owner, name)) = column
+            && let Some(rust_type) = self.overrides.get(&format!("{}.{}", owner, name))
+        {
+            return MappedType::Override(rust_type);
+        }
+
+        let unmodified = strip_type_modifiers(data_type);
+        if let Some(rust_type) = self.overrides.get(data_type).or_else(|| self.overrides.get(&unmodified)) {
+            return MappedType::Override(rust_type);
+        }
+
+        if self.user_types.contains(data_type) {
+            return MappedType::User(data_type);
+        }
+
+        match builtin_rust_type(&unmodified) {
+            Some(rust_type) => MappedType::Builtin(rust_type),
+            None => MappedType::Unknown,
+        }
+    }
+
+    /// The id newtype a table defines for its own primary key
+    fn own_id_type(&self, table: &str) -> Option<&IdType> {
+        self.id_types.values().find(|id_type| id_type.table == table)
+    }
+
+    /// The id newtype for a column, unless `table.column` overrides its type
+    fn id_type(&self, column: ColumnKey) -> Option<&IdType> {
+        let (owner, name) = column?;
+        if self.overrides.contains_key(&format!("{}.{}", owner, name)) {
+            return None;
+        }
+        self.id_types.get(&(owner.to_string(), name.to_string()))
+    }
+
+    /// Tables with a column typed as `id_type`, its own table first
+    fn id_type_users(&self, id_type: &IdType) -> Vec<&str> {
+        let mut tables: Vec<&str> = self
+            .id_types
+            .keys()
+            .filter(|(owner, name)| self.id_type(Some((owner, name))).is_some_and(|t| t.name == id_type.name))
+            .map(|(owner, _)| owner.as_str())
+            .collect();
+        tables.sort_by_key(|table| (*table != id_type.table, *table));
+        tables.dedup();
+        tables
+    }
+
+    fn rust_type(&self, column: ColumnKey, data_type: &str, is_nullable: bool) -> String {
+        let base_type = match self.id_type(column) {
+            Some(id_type) => id_type.name.clone(),
+            None => self.mapped_rust_type(column, data_type),
+        };
+
+        if is_nullable {
+            format!("Option<{}>", base_type)
+        } else {
+            base_type
+        }
+    }
+
+    /// The Rust type from the type mapping alone, ignoring id newtypes
+    fn mapped_rust_type(&self, column: ColumnKey, data_type: &str) -> String {
+        match self.map(column, data_type) {
+            MappedType::Override(rust_type) => rust_type.to_string(),
+            MappedType::User(user_type) => to_struct_name(user_type),
+            MappedType::Builtin(rust_type) => rust_type.to_string(),
+            MappedType::Unknown => "String".to_string(),
+        }
+    }
+
+    /// `use` items needed by the Rust types of the given columns
+    fn imports<'c>(&self, columns: impl IntoIterator<Item = (ColumnKey<'c>, &'c str)>) -> TokenStream {
+        let mut needs_chrono = false;
+        let mut needs_decimal = false;
+        let mut needs_uuid = false;
+        let mut needs_json = false;
+        let mut used_user_types = Vec::new();
+        let mut used_id_types = Vec::new();
+
+        for (column, data_type) in columns {
+            // Another table's id newtype replaces the column's own type
+            if let (Some(id_type), Some((owner, _))) = (self.id_type(column), column)
+                && id_type.table != owner
+            {
+                used_id_types.push((id_type.table.clone(), id_type.name.clone()));
+                continue;
+            }
+
+            match self.map(column, data_type) {
+                MappedType::User(user_type) => used_user_types.push(user_type.to_string()),
+                MappedType::Builtin(rust_type) => {
+                    needs_chrono |= rust_type.starts_with("chrono::");
+                    needs_decimal |= rust_type == "Decimal";
+                    needs_uuid |= rust_type == "Uuid";
+                    needs_json |= rust_type.starts_with("serde_json::");
+                }
+                // Overrides are written as full paths and need no import
+                MappedType::Override(_) | MappedType::Unknown => {}
+            }
+        }
+
+        let mut items = Vec::new();
+
+        if needs_chrono {
+            items.push(quote! { use chrono; });
+        }
+        if needs_decimal {
+            items.push(quote! { use rust_decimal::Decimal; });
+        }
+        if needs_uuid {
+            items.push(quote! { use uuid::Uuid; });
+        }
+        if needs_json {
+            items.push(quote! { use serde_json; });
+        }
+
+        used_user_types.sort();
+        used_user_types.dedup();
+        for user_type in used_user_types {
+            let module = ident(&module_name(&user_type));
+            let name = ident(&to_struct_name(&user_type));
+            items.push(quote! { use super::#module::#name; });
+        }
+
+        used_id_types.sort();
+        used_id_types.dedup();
+        for (table, id_type) in used_id_types {
+            let module = ident(&module_name(&table));
+            let name = ident(&id_type);
+            items.push(quote! { use super::#module::#name; });
+        }
+
+        quote! { #(#items)* }
+    }
+
+    /// One warning per column whose type has no mapping and falls back to `String`
+    fn unknown_type_warnings(&self, schema: &TomlSchema) -> Vec<String> {
+        let mut warnings: Vec<String> = typed_columns(schema)
+            .into_iter()
+            .filter(|(owner, name, data_type)| {
+                matches!(self.map(Some((owner, name)), data_type), MappedType::Unknown)
+            })
+            .map(|(owner, name, data_type)| {
+                format!(
+                    "No Rust type for `{}` (`{}.{}`), using String; map it under [codegen.types]",
+                    data_type, owner, name
+                )
+            })
+            .collect();
+
+        for domain in &schema.domain {
+            if matches!(self.map(None, &domain.base_type), MappedType::Unknown) {
+                warnings.push(format!(
+                    "No Rust type for `{}` (domain `{}`), using String; map it under [codegen.types]",
+                    domain.base_type, domain.name
+                ));
+            }
+        }
+
+        warnings
+    }
+}
+
+/// Maps Postgres types for a target besides Rust through the Rust type of
+/// each column, after the target's own `[codegen.<target>.types]`. Id
+/// newtypes serialize as their inner type, so they are left out.
+struct TargetTypes<'a, T> {
+    rust: TypeMap<'a>,
+    overrides: &'a HashMap<String, T>,
+}
+
+/// Where a column's type came from for a target besides Rust
+enum TargetType<'a, T> {
+    Override(&'a T),
+    Rust(MappedType<'a>),
+}
+
+impl<'a, T> TargetTypes<'a, T> {
+    fn new(schema: &'a TomlSchema, config: &'a CodegenConfig, overrides: &'a HashMap<String, T>) -> Self {
+        TargetTypes {
+            rust: TypeMap {
+                user_types: user_type_names(schema),
+                overrides: &config.types,
+                id_types: IdTypes::new(),
+            },
+            overrides,
+        }
+    }
+
+    fn map<'s>(&'s self, column: ColumnKey, data_type: &'s str) -> TargetType<'s, T> {
+        let target_override = column
+            .and_then(|(owner, name)| self.overrides.get(&format!("{}.{}", owner, name)))
+            .or_else(|| self.overrides.get(data_type))
+            .or_else(|| self.overrides.get(&strip_type_modifiers(data_type)));
+
+        match target_override {
+            Some(target_type) => TargetType::Override(target_type),
+            None => TargetType::Rust(self.rust.map(column, data_type)),
+        }
+    }
+
+    /// One warning per column without a mapping for `target`, such as
+    /// "TypeScript type", configured under `[codegen.<section>.types]`.
+    /// Unknown types fall back to a string and Rust overrides to `any_value`.
+    fn warnings(&self, schema: &TomlSchema, target: &str, section: &str, any_value: &str) -> Vec<String> {
+        let columns = typed_columns(schema)
+            .into_iter()
+            .map(|(owner, name, data_type)| (Some((owner, name)), format!("`{}.{}`", owner, name), data_type))
+            .chain(
+                schema
+                    .domain
+                    .iter()
+                    .map(|d| (None, format!("domain `{}`", d.name), d.base_type.as_str())),
+            );
+
+        let mut warnings = Vec::new();
+        for (column, described, data_type) in columns {
+            match self.map(column, data_type) {
+                TargetType::Rust(MappedType::Unknown) => warnings.push(format!(
+                    "No {} for `{}` ({}), using string; map it under [codegen.{}.types]",
+                    target, data_type, described, section
+                )),
+                TargetType::Rust(MappedType::Override(rust_type)) => warnings.push(format!(
+                    "No {} for the Rust type `{}` ({}), {}; map it under [codegen.{}.types]",
+                    target, rust_type, described, any_value, section
+                )),
+                _ => {}
+            }
+        }
+        warnings
+    }
+}
+
+fn user_type_names(schema: &TomlSchema) -> HashSet<&str> {
+    schema
+        .domain
+        .iter()
+        .map(|d| d.name.as_str())
+        .chain(schema.composite_type.iter().map(|c| c.name.as_str()))
+        .collect()
+}
+
+/// (owner, name, data type) of every table, view and composite type column
+fn typed_columns(schema: &TomlSchema) -> Vec<(&str, &str, &str)> {
+    let mut columns = Vec::new();
+    for table in &schema.table {
+        columns.extend(table.column.iter().map(|c| (table.name.as_str(), c.name.as_str(), c.data_type.as_str())));
+    }
+    for view in &schema.view {
+        columns.extend(view.column.iter().map(|c| (view.name.as_str(), c.name.as_str(), c.data_type.as_str())));
+    }
+    for composite in &schema.composite_type {
+        columns.extend(
+            composite
+                .attribute
+                .iter()
+                .map(|a| (composite.name.as_str(), a.name.as_str(), a.data_type.as_str())),
+        );
+    }
+    columns
+}
+
+/// `numeric(12,2)` and `timestamp(3) with time zone` map like their unmodified types
+fn strip_type_modifiers(data_type: &str) -> String {
+    match (data_type.find('('), data_type.find(')')) {
+        (Some(start), Some(end)) if start < end => {
+            format!("{}{}", data_type[..start].trim_end(), &data_type[end + 1..])
+        }
+        _ => data_type.to_string(),
+    }
+}
+
+fn builtin_rust_type(data_type: &str) -> Option<&'static str> {
+    let rust_type = match data_type {
+        "integer" | "int" | "int4" | "serial" | "serial4" => "i32",
+        "bigint" | "int8" | "bigserial" | "serial8" => "i64",
+        "smallint" | "int2" | "smallserial" | "serial2" => "i16",
+        "text" | "character varying" | "varchar" | "char" | "character" => "String",
+        "boolean" | "bool" => "bool",
+        "real" | "float4" => "f32",
+        "double precision" | "float8" => "f64",
+        "timestamp without time zone" | "timestamp" => "chrono::NaiveDateTime",
+        "timestamp with time zone" | "timestamptz" => "chrono::DateTime<chrono::Utc>",
+        "date" => "chrono::NaiveDate",
+        "time" | "time without time zone" => "chrono::NaiveTime",
+        "numeric" | "decimal" => "Decimal",
+        "uuid" => "Uuid",
+        "json" | "jsonb" => "serde_json::Value",
+        "bytea" => "Vec<u8>",
+        "citext" => "String",
+        "hstore" => "sqlx::postgres::types::PgHstore",
+        "ltree" => "sqlx::postgres::types::PgLTree",
+        "lquery" => "sqlx::postgres::types::PgLQuery",
+        "cube" => "sqlx::postgres::types::PgCube",
+        _ => return None,
+    };
+
+    Some(rust_type)
+}
+
+fn to_struct_name(name: &str) -> String {
+    let struct_name: String = to_snake_case(name)
+        .split('_')
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                None => String::new(),
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+            }
+        })
+        .collect();
+
+    match struct_name.chars().next() {
+        None => "Row".to_string(),
+        Some(first) if first.is_ascii_digit() => format!("_{}", struct_name),
+        _ if struct_name == "Self" => "Self_".to_string(),
+        _ => struct_name,
+    }
+}
+
+/// A snake_case identifier, with keywords escaped as raw identifiers
+fn field_name(name: &str) -> String {
+    let ident = snake_ident(name, "column");
+    if !RUST_KEYWORDS.contains(&ident.as_str()) {
+        ident
+    } else if RAW_FORBIDDEN.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        format!("r#{}", ident)
+    }
+}
+
+/// Module and file name for a generated type. `r#mod` would still load
+/// `mod.rs`, so keywords get a trailing underscore instead.
+fn module_name(name: &str) -> String {
+    let ident = snake_ident(name, "table");
+    if RUST_KEYWORDS.contains(&ident.as_str()) {
+        format!("{}_", ident)
+    } else {
+        ident
+    }
+}
+
+/// snake_case that is a valid identifier, using `fallback` for names with
+/// no usable characters
+fn snake_ident(name: &str, fallback: &str) -> String {
+    let ident = to_snake_case(name);
+    match ident.chars().next() {
+        None => fallback.to_string(),
+        Some(first) if first.is_ascii_digit() => format!("_{}", ident),
+        _ => ident,
+    }
+}
+
+/// `userID`, `User Name` and `HTTPStatus` become `user_id`, `user_name`
+/// and `http_status`; anything that is not ASCII alphanumeric separates words
+fn to_snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !snake.is_empty() && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            continue;
+        }
+
+        if c.is_ascii_uppercase() && i > 0 && !snake.ends_with('_') {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            if prev.is_ascii_lowercase() || prev.is_ascii_digit() || (prev.is_ascii_uppercase() && next_is_lower) {
+                snake.push('_');
+            }
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+
+    snake.trim_end_matches('_').to_string()
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "break", "const", "continue", "crate", "else", "enum", "extern", "false", "fn", "for",
+    "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "self", "static", "struct", "super", "trait", "true", "type", "unsafe", "use", "where",
+    "while", "async", "await", "dyn", "abstract", "become", "box", "do", "final", "macro",
+    "override", "priv", "typeof", "unsized", "virtual", "yield", "try", "gen",
+];
+
+/// Keywords that cannot be raw identifiers
+const RAW_FORBIDDEN: &[&str] = &["crate", "self", "super"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::RelationNames;
+    use std::collections::BTreeMap;
+
+    fn render(schema: &str, config: &CodegenConfig) -> Result<Vec<(PathBuf, String)>> {
+        let mut schema: TomlSchema = toml::from_str(schema).unwrap();
+        for table in &mut schema.table {
+            table.expand(&BTreeMap::new()).unwrap();
+        }
+        render_rust(&schema, "models", config)
+    }
+
+    fn file<'f>(files: &'f [(PathBuf, String)], name: &str) -> &'f str {
+        files.iter().find(|(path, _)| path.ends_with(name)).map(|(_, content)| content.as_str()).unwrap()
+    }
+
+    #[test]
+    fn validate_import() {
+        let config = CodegenConfig { validate: true, ..Default::default() };
+        let files = render(
+            r#"
+            [[domain]]
+            name = "percent"
+            base_type = "integer"
+            check = "VALUE BETWEEN 0 AND 100"
+
+            [[domain]]
+            name = "email"
+            base_type = "text"
+            check = "VALUE ~ '@'"
+
+            [[composite_type]]
+            name = "address"
+            attribute = [{ name = "street", data_type = "varchar(120)" }]
+
+            [[table]]
+            name = "users"
+            columns = { id = "bigint primary key", score = "percent", email = "email", home = "address" }
+            "#,
+            &config,
+        )
+        .unwrap();
+
+        // The derived code calls `validate()` on nested fields as a method
+        for name in ["percent.rs", "address.rs", "users.rs"] {
+            assert!(file(&files, name).contains("use validator::Validate;\n"), "{} lacks the import", name);
+        }
+        assert!(file(&files, "percent.rs").contains("impl Validate for Percent {"));
+        assert!(file(&files, "address.rs").contains("#[derive(Debug, Clone, sqlx::Type, Validate)]"));
+        assert!(file(&files, "users.rs").contains("#[derive(Debug, Clone, FromRow, Validate)]"));
+        assert!(file(&files, "users.rs").contains("#[validate(nested)]\n    pub score: Option<Percent>,"));
+
+        // Unvalidated CHECKs leave the domain without an impl to import for
+        assert!(!file(&files, "email.rs").contains("validator"));
+    }
+
+    #[test]
+    fn relation_field_collisions() {
+        let schema = r#"
+            [[table]]
+            name = "users"
+            columns = { id = "bigint primary key" }
+
+            [[table]]
+            name = "posts"
+            columns = { id = "bigint primary key", author = "text", author_id = "bigint references users" }
+            "#;
+
+        let error = render(schema, &CodegenConfig::default()).unwrap_err().to_string();
+        assert!(error.contains("two `author` fields"), "{}", error);
+        assert!(error.contains("\"posts.author_id\""), "{}", error);
+
+        let mut config = CodegenConfig::default();
+        config.relations.insert(
+            "posts.author_id".to_string(),
+            RelationNames { belongs_to: Some("writer".to_string()), ..Default::default() },
+        );
+        let files = render(schema, &config).unwrap();
+        assert!(file(&files, "posts.rs").contains("pub writer: Option<User>,"));
+    }
+
+    #[test]
+    fn struct_name_collisions() {
+        let schema = r#"
+            [[table]]
+            name = "person"
+            columns = { id = "bigint primary key" }
+
+            [[view]]
+            name = "people"
+            definition = "SELECT id FROM person"
+            column = [{ name = "id", data_type = "bigint", is_nullable = false }]
+            "#;
+
+        let error = render(schema, &CodegenConfig::default()).unwrap_err().to_string();
+        assert!(error.contains("table `person` and view `people` generate the struct `Person`"), "{}", error);
+
+        let mut config = CodegenConfig::default();
+        config.structs.insert("people".to_string(), "PersonRow".to_string());
+        let files = render(schema, &config).unwrap();
+        assert!(file(&files, "people.rs").contains("pub struct PersonRow {"));
+    }
+
+    #[test]
+    fn check_condition_between() {
+        assert_eq!(
+            check_condition("VALUE BETWEEN 0 AND 100", "i32").as_deref(),
+            Some("self.0 >= 0 && self.0 <= 100")
+        );
+    }
+
+    #[test]
+    fn check_condition_literal_on_left() {
+        assert_eq!(
+            check_condition("0 < VALUE AND 10 >= VALUE", "i64").as_deref(),
+            Some("self.0 > 0 && self.0 <= 10")
+        );
+    }
+
+    #[test]
+    fn check_condition_negative_numbers() {
+        assert_eq!(check_condition("VALUE > -1", "i16").as_deref(), Some("self.0 > -1"));
+        // As Postgres deparses it
+        assert_eq!(
+            check_condition("((VALUE >= '-5'::integer) AND (VALUE <= 5))", "i32").as_deref(),
+            Some("self.0 >= -5 && self.0 <= 5")
+        );
+    }
+
+    #[test]
+    fn check_condition_floats() {
+        assert_eq!(
+            check_condition("0 < VALUE AND VALUE <= 1e6", "f64").as_deref(),
+            Some("self.0 > 0.0 && self.0 <= 1000000.0")
+        );
+        assert_eq!(
+            check_condition("(((0)::double precision < VALUE) AND (VALUE <= '1.5'::double precision))", "f32")
+                .as_deref(),
+            Some("self.0 > 0.0 && self.0 <= 1.5")
+        );
+        assert_eq!(check_condition("VALUE > 1.5", "i32"), None);
+    }
+
+    #[test]
+    fn check_condition_text_casts() {
+        assert_eq!(
+            check_condition("char_length(VALUE) >= 3", "String").as_deref(),
+            Some("self.0.chars().count() >= 3")
+        );
+        assert_eq!(
+            check_condition("(char_length((VALUE)::text) >= 3)", "String").as_deref(),
+            Some("self.0.chars().count() >= 3")
+        );
+        assert_eq!(
+            check_condition("length(VALUE::character varying(20)) BETWEEN 1 AND 20", "String").as_deref(),
+            Some("self.0.chars().count() >= 1 && self.0.chars().count() <= 20")
+        );
+    }
+
+    #[test]
+    fn check_condition_rejects_anything_else() {
+        assert_eq!(check_condition("VALUE ~ '^[a-z]+$'", "String"), None);
+        assert_eq!(check_condition("VALUE > 0 OR VALUE < -10", "i32"), None);
+        assert_eq!(check_condition("VALUE > 0", "String"), None);
+        assert_eq!(check_condition("length(VALUE) > 2", "i32"), None);
+        assert_eq!(check_condition("VALUE > other", "i32"), None);
+        assert_eq!(check_condition("VALUE <> 'value'", "i32"), None);
+        assert_eq!(check_condition("VALUE > 1 AND", "i32"), None);
+    }
+}
@@ -0,0 +1,298 @@
+//! OpenAPI 3.1 component schemas for the JSON the Rust models serialize to,
+//! with `New*` and `*Patch` variants for insert and update payloads.
+
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::path::{Path, PathBuf};
+
+use super::{
+    build_relations, build_struct_names, enum_values, field_name, model_options, to_struct_name,
+    varchar_length, ColumnKey, MappedType, ModelOptions, Relations, StructNames, TargetType, TargetTypes,
+};
+use crate::config::CodegenConfig;
+use crate::schema::{Column, CompositeType, Domain};
+use crate::toml_schema::{TomlSchema, TomlTable, TomlView};
+
+/// An `openapi.json` holding only `components.schemas`, to merge into an API spec
+pub(super) fn render(schema: &TomlSchema, output_path: &str, config: &CodegenConfig) -> Result<Vec<(PathBuf, String)>> {
+    let relations = build_relations(schema, config)?;
+    let struct_names = build_struct_names(schema, config)?;
+    let types = SchemaTypes {
+        target: TargetTypes::new(schema, config, &config.openapi.types),
+    };
+
+    for warning in types.target.warnings(schema, "OpenAPI schema", "openapi", "allowing any value") {
+        println!("warning: {}", warning);
+    }
+
+    let mut schemas = Map::new();
+    for domain in &schema.domain {
+        schemas.insert(to_struct_name(&domain.name), domain_schema(domain, &types));
+    }
+    for composite in &schema.composite_type {
+        schemas.insert(to_struct_name(&composite.name), composite_schema(composite, &types, config)?);
+    }
+    for table in &schema.table {
+        let name = &struct_names[&table.name];
+        let options = model_options(config, &table.name);
+        schemas.insert(name.clone(), row_schema(table, &relations, &struct_names, &types, &options)?);
+        schemas.insert(format!("New{}", name), new_schema(table, &types, &options)?);
+        schemas.insert(format!("{}Patch", name), patch_schema(table, &types, &options)?);
+    }
+    for view in &schema.view {
+        let options = model_options(config, &view.name);
+        schemas.insert(struct_names[&view.name].clone(), view_schema(view, &types, &options)?);
+    }
+
+    let document = json!({
+        "openapi": "3.1.0",
+        "info": { "title": "Database models", "version": "1.0.0" },
+        "components": { "schemas": schemas },
+    });
+    let mut content = serde_json::to_string_pretty(&document)?;
+    content.push('\n');
+
+    Ok(vec![(Path::new(output_path).join("openapi.json"), content)])
+}
+
+// ============ Type Mapping ============
+
+/// Maps Postgres types to JSON Schemas: `[codegen.openapi.types]` first,
+/// then the Rust mapping
+struct SchemaTypes<'a> {
+    target: TargetTypes<'a, Value>,
+}
+
+impl SchemaTypes<'_> {
+    fn map(&self, column: ColumnKey, data_type: &str) -> Value {
+        match self.target.map(column, data_type) {
+            TargetType::Override(schema) => schema.clone(),
+            TargetType::Rust(MappedType::User(user_type)) => schema_ref(&to_struct_name(user_type)),
+            TargetType::Rust(MappedType::Builtin(rust_type)) => builtin_schema(rust_type, data_type),
+            // The Rust models fall back to String as well
+            TargetType::Rust(MappedType::Unknown) => string_schema(data_type),
+            TargetType::Rust(MappedType::Override(_)) => json!({}),
+        }
+    }
+
+    /// A column's schema, allowing null when the column is nullable
+    fn column_schema(&self, owner: &str, column: &Column) -> Value {
+        let schema = self.map(Some((owner, &column.name)), &column.data_type);
+        let mut schema = if column.is_nullable { nullable(schema) } else { schema };
+        if let Some(comment) = &column.comment
+            && let Some(object) = schema.as_object_mut()
+        {
+            object.insert("description".to_string(), json!(comment));
+        }
+        schema
+    }
+}
+
+/// The JSON Schema of a built-in Rust type's JSON
+fn builtin_schema(rust_type: &str, data_type: &str) -> Value {
+    match rust_type {
+        "i16" | "i32" => json!({ "type": "integer", "format": "int32" }),
+        "i64" => json!({ "type": "integer", "format": "int64" }),
+        "f32" => json!({ "type": "number", "format": "float" }),
+        "f64" => json!({ "type": "number", "format": "double" }),
+        "bool" => json!({ "type": "boolean" }),
+        "String" => string_schema(data_type),
+        "Uuid" => json!({ "type": "string", "format": "uuid" }),
+        "chrono::DateTime<chrono::Utc>" => json!({ "type": "string", "format": "date-time" }),
+        "chrono::NaiveDate" => json!({ "type": "string", "format": "date" }),
+        // Without an offset these are not RFC 3339 `date-time` or `time` values
+        "chrono::NaiveDateTime" | "chrono::NaiveTime" => json!({ "type": "string" }),
+        // rust_decimal serializes as a string to keep its precision
+        "Decimal" => json!({ "type": "string", "format": "decimal" }),
+        "Vec<u8>" => json!({ "type": "array", "items": { "type": "integer" } }),
+        "sqlx::postgres::types::PgHstore" => {
+            json!({ "type": "object", "additionalProperties": { "type": ["string", "null"] } })
+        }
+        _ => json!({}),
+    }
+}
+
+fn string_schema(data_type: &str) -> Value {
+    match varchar_length(data_type) {
+        Some(max) => json!({ "type": "string", "maxLength": max }),
+        None => json!({ "type": "string" }),
+    }
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({ "$ref": format!("#/components/schemas/{}", name) })
+}
+
+/// A schema that also allows null, as OpenAPI 3.1 has no `nullable`
+fn nullable(mut schema: Value) -> Value {
+    let Some(object) = schema.as_object_mut() else {
+        return schema;
+    };
+    if object.is_empty() {
+        return schema;
+    }
+
+    match object.get("type").cloned() {
+        Some(Value::String(type_name)) => {
+            object.insert("type".to_string(), json!([type_name, "null"]));
+            if let Some(Value::Array(values)) = object.get_mut("enum") {
+                values.push(Value::Null);
+            }
+            schema
+        }
+        _ => json!({ "anyOf": [schema, { "type": "null" }] }),
+    }
+}
+
+// ============ Component Schemas ============
+
+/// Domains take their base type's schema; a CHECK listing allowed strings
+/// gives an enum
+fn domain_schema(domain: &Domain, types: &SchemaTypes) -> Value {
+    let mut schema = types.map(None, &domain.base_type);
+    if let Some(values) = domain.check.as_deref().and_then(enum_values)
+        && schema.get("type") == Some(&json!("string"))
+    {
+        schema = json!({ "type": "string", "enum": values });
+    }
+    if let Some(object) = schema.as_object_mut() {
+        object.insert(
+            "description".to_string(),
+            json!(format!("`{}` domain over {}", domain.name, domain.base_type)),
+        );
+    }
+    schema
+}
+
+fn composite_schema(composite: &CompositeType, types: &SchemaTypes, config: &CodegenConfig) -> Result<Value> {
+    let options = model_options(config, &composite.name);
+    let mut properties = Map::new();
+    for attr in &composite.attribute {
+        // Postgres can't enforce NOT NULL on composite attributes
+        let schema = nullable(types.map(Some((&composite.name, &attr.name)), &attr.data_type));
+        properties.insert(options.serde_name(&field_name(&attr.name))?, schema);
+    }
+    let required: Vec<String> = properties.keys().cloned().collect();
+
+    Ok(object_schema(
+        Some(format!("`{}` composite type", composite.name)),
+        properties,
+        required,
+    ))
+}
+
+/// A row as the Rust model serializes it: every column, plus relations once loaded
+fn row_schema(
+    table: &TomlTable,
+    relations: &Relations,
+    struct_names: &StructNames,
+    types: &SchemaTypes,
+    options: &ModelOptions,
+) -> Result<Value> {
+    let mut properties = column_properties(&table.name, table.column.iter(), types, options)?;
+    let required: Vec<String> = properties.keys().cloned().collect();
+
+    let mut relation = |description: String, field: &str, table: &str, many: bool| -> Result<()> {
+        let target = schema_ref(&struct_names[table]);
+        let mut schema = if many { json!({ "type": "array", "items": target }) } else { target };
+        if let Some(object) = schema.as_object_mut() {
+            object.insert("description".to_string(), json!(description));
+        }
+        properties.insert(options.serde_name(&field_name(field))?, schema);
+        Ok(())
+    };
+
+    for r in relations.belongs_to.get(&table.name).into_iter().flatten() {
+        relation(format!("belongs_to `{}`", r.table), &r.field, &r.table, false)?;
+    }
+    for r in relations.has_one.get(&table.name).into_iter().flatten() {
+        relation(format!("has_one `{}`", r.table), &r.field, &r.table, false)?;
+    }
+    for r in relations.has_many.get(&table.name).into_iter().flatten() {
+        relation(format!("has_many `{}`", r.table), &r.field, &r.table, true)?;
+    }
+    for r in relations.many_to_many.get(&table.name).into_iter().flatten() {
+        let description = format!("many_to_many `{}` through `{}`", r.table, r.join_table);
+        relation(description, &r.field, &r.table, true)?;
+    }
+
+    Ok(object_schema(table.comment.clone(), properties, required))
+}
+
+/// An insert payload; columns that are nullable, have a default or are
+/// generated may be left out
+fn new_schema(table: &TomlTable, types: &SchemaTypes, options: &ModelOptions) -> Result<Value> {
+    let properties = column_properties(&table.name, table.column.iter(), types, options)?;
+    let required = table
+        .column
+        .iter()
+        .filter(|c| !c.is_nullable && c.default.is_none() && !is_generated(c))
+        .map(|c| options.serde_name(&field_name(&c.name)))
+        .collect::<Result<Vec<_>>>()?;
+
+    let description = format!("A new `{}` row", table.name);
+    Ok(object_schema(Some(description), properties, required))
+}
+
+/// Serial columns and those drawing from a sequence, whose values the
+/// database fills in
+fn is_generated(column: &Column) -> bool {
+    let serial = matches!(
+        column.data_type.trim().to_lowercase().as_str(),
+        "serial" | "serial4" | "bigserial" | "serial8" | "smallserial" | "serial2"
+    );
+    serial || column.default.as_deref().is_some_and(|d| d.trim_start().to_lowercase().starts_with("nextval("))
+}
+
+/// An update payload of any columns but the primary key
+fn patch_schema(table: &TomlTable, types: &SchemaTypes, options: &ModelOptions) -> Result<Value> {
+    let primary_key: &[String] = table
+        .index
+        .iter()
+        .find(|i| i.name.ends_with("_pkey"))
+        .map(|pk| pk.columns.as_slice())
+        .unwrap_or_default();
+    let columns = table.column.iter().filter(|c| !primary_key.contains(&c.name));
+    let properties = column_properties(&table.name, columns, types, options)?;
+
+    let description = format!("Changes to a `{}` row", table.name);
+    Ok(object_schema(Some(description), properties, Vec::new()))
+}
+
+fn view_schema(view: &TomlView, types: &SchemaTypes, options: &ModelOptions) -> Result<Value> {
+    let properties = column_properties(&view.name, view.column.iter(), types, options)?;
+    let required: Vec<String> = properties.keys().cloned().collect();
+    let kind = if view.materialized { "materialized view" } else { "view" };
+
+    Ok(object_schema(
+        Some(format!("Read-only row of the `{}` {}", view.name, kind)),
+        properties,
+        required,
+    ))
+}
+
+fn column_properties<'c>(
+    owner: &str,
+    columns: impl Iterator<Item = &'c Column>,
+    types: &SchemaTypes,
+    options: &ModelOptions,
+) -> Result<Map<String, Value>> {
+    let mut properties = Map::new();
+    for col in columns {
+        properties.insert(options.serde_name(&field_name(&col.name))?, types.column_schema(owner, col));
+    }
+    Ok(properties)
+}
+
+fn object_schema(description: Option<String>, properties: Map<String, Value>, required: Vec<String>) -> Value {
+    let mut schema = Map::new();
+    schema.insert("type".to_string(), json!("object"));
+    if let Some(description) = description {
+        schema.insert("description".to_string(), json!(description));
+    }
+    schema.insert("properties".to_string(), Value::Object(properties));
+    if !required.is_empty() {
+        schema.insert("required".to_string(), json!(required));
+    }
+    Value::Object(schema)
+}
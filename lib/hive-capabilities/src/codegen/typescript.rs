@@ -0,0 +1,332 @@
+//! TypeScript interfaces, and optionally zod schemas, for the JSON the Rust
+//! models serialize to. Types follow the Rust mapping, and property names
+//! follow each model's `#[serde(rename_all)]`.
+
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use super::{
+    build_relations, build_struct_names, enum_values, field_name, model_options, to_struct_name,
+    varchar_length, ColumnKey, MappedType, ModelOptions, Relations, StructNames, TargetType, TargetTypes,
+};
+use crate::config::CodegenConfig;
+use crate::schema::{Column, CompositeType, Domain};
+use crate::toml_schema::{TomlSchema, TomlTable, TomlView};
+
+/// Every model in a single `index.ts`
+pub(super) fn render(schema: &TomlSchema, output_path: &str, config: &CodegenConfig) -> Result<Vec<(PathBuf, String)>> {
+    let relations = build_relations(schema, config)?;
+    let struct_names = build_struct_names(schema, config)?;
+    let types = TsTypes {
+        target: TargetTypes::new(schema, config, &config.typescript.types),
+        composites: schema.composite_type.iter().map(|c| c.name.as_str()).collect(),
+    };
+
+    for warning in types.target.warnings(schema, "TypeScript type", "typescript", "using unknown") {
+        println!("warning: {}", warning);
+    }
+
+    let zod = config.typescript.zod;
+    let mut items = Vec::new();
+    if zod {
+        items.push("import { z } from \"zod\";\n".to_string());
+    }
+
+    // Domains come first so zod schemas can use them directly
+    for domain in &schema.domain {
+        items.push(domain_items(domain, &types, zod));
+    }
+    for composite in &schema.composite_type {
+        items.push(composite_items(composite, &types, config)?);
+    }
+    for table in &schema.table {
+        items.push(table_items(table, &relations, &struct_names, &types, config)?);
+    }
+    for view in &schema.view {
+        items.push(view_items(view, &struct_names[&view.name], &types, config)?);
+    }
+
+    Ok(vec![(Path::new(output_path).join("index.ts"), items.join("\n"))])
+}
+
+// ============ Type Mapping ============
+
+/// Maps Postgres types to TypeScript: `[codegen.typescript.types]` first,
+/// then the Rust mapping
+struct TsTypes<'a> {
+    target: TargetTypes<'a, String>,
+    /// Composite types, which zod schemas reference lazily since one may be
+    /// declared after a type using it
+    composites: HashSet<&'a str>,
+}
+
+/// A TypeScript type and the zod schema validating it
+struct TsType {
+    ts: String,
+    zod: String,
+}
+
+impl TsType {
+    fn nullable(self) -> TsType {
+        TsType {
+            ts: format!("{} | null", self.ts),
+            zod: format!("{}.nullable()", self.zod),
+        }
+    }
+}
+
+impl TsTypes<'_> {
+    fn map(&self, column: ColumnKey, data_type: &str) -> TsType {
+        match self.target.map(column, data_type) {
+            TargetType::Override(ts) => TsType {
+                ts: ts.clone(),
+                zod: format!("z.custom<{}>()", ts),
+            },
+            TargetType::Rust(MappedType::User(user_type)) => {
+                let name = to_struct_name(user_type);
+                let zod = if self.composites.contains(user_type) {
+                    format!("z.lazy(() => {}Schema)", name)
+                } else {
+                    format!("{}Schema", name)
+                };
+                TsType { ts: name, zod }
+            }
+            TargetType::Rust(MappedType::Builtin(rust_type)) => builtin_ts_type(rust_type, data_type),
+            // The Rust models fall back to String as well
+            TargetType::Rust(MappedType::Unknown) => string_type(data_type),
+            TargetType::Rust(MappedType::Override(_)) => TsType {
+                ts: "unknown".to_string(),
+                zod: "z.unknown()".to_string(),
+            },
+        }
+    }
+
+    fn column_type(&self, owner: &str, column: &Column) -> TsType {
+        let mapped = self.map(Some((owner, &column.name)), &column.data_type);
+        if column.is_nullable { mapped.nullable() } else { mapped }
+    }
+}
+
+/// The TypeScript form of a built-in Rust type's JSON
+fn builtin_ts_type(rust_type: &str, data_type: &str) -> TsType {
+    let (ts, zod) = match rust_type {
+        "i16" | "i32" | "i64" => ("number", "z.number().int()"),
+        "f32" | "f64" => ("number", "z.number()"),
+        "bool" => ("boolean", "z.boolean()"),
+        "String" => return string_type(data_type),
+        "Uuid" => ("string", "z.string().uuid()"),
+        // chrono types and Decimal serialize as strings
+        "Decimal" => ("string", "z.string()"),
+        _ if rust_type.starts_with("chrono::") => ("string", "z.string()"),
+        "Vec<u8>" => ("number[]", "z.array(z.number().int())"),
+        "sqlx::postgres::types::PgHstore" => {
+            ("Record<string, string | null>", "z.record(z.string(), z.string().nullable())")
+        }
+        _ => ("unknown", "z.unknown()"),
+    };
+
+    TsType {
+        ts: ts.to_string(),
+        zod: zod.to_string(),
+    }
+}
+
+fn string_type(data_type: &str) -> TsType {
+    TsType {
+        ts: "string".to_string(),
+        zod: match varchar_length(data_type) {
+            Some(max) => format!("z.string().max({})", max),
+            None => "z.string()".to_string(),
+        },
+    }
+}
+
+// ============ Generated Items ============
+
+/// A property of a generated interface
+struct Property {
+    doc: Option<String>,
+    name: String,
+    ts_type: TsType,
+    /// Relations are only present once loaded
+    optional: bool,
+}
+
+/// Domains become type aliases; a CHECK listing allowed strings gives a union
+fn domain_items(domain: &Domain, types: &TsTypes, zod: bool) -> String {
+    let name = to_struct_name(&domain.name);
+    let base = types.map(None, &domain.base_type);
+    let values = domain.check.as_deref().and_then(enum_values).filter(|_| base.ts == "string");
+
+    let ts_type = match &values {
+        Some(values) => TsType {
+            ts: values.iter().map(|v| string_literal(v)).collect::<Vec<_>>().join(" | "),
+            zod: format!(
+                "z.enum([{}])",
+                values.iter().map(|v| string_literal(v)).collect::<Vec<_>>().join(", ")
+            ),
+        },
+        None => base,
+    };
+
+    let mut lines = doc_lines(&format!("`{}` domain over {}", domain.name, domain.base_type), "");
+    lines.push(format!("export type {} = {};", name, ts_type.ts));
+    if zod {
+        lines.push(String::new());
+        lines.push(format!("export const {}Schema: z.ZodType<{}> = {};", name, name, ts_type.zod));
+    }
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn composite_items(composite: &CompositeType, types: &TsTypes, config: &CodegenConfig) -> Result<String> {
+    let options = model_options(config, &composite.name);
+    let mut properties = Vec::new();
+    for attr in &composite.attribute {
+        properties.push(Property {
+            doc: None,
+            name: options.serde_name(&field_name(&attr.name))?,
+            // Postgres can't enforce NOT NULL on composite attributes
+            ts_type: types.map(Some((&composite.name, &attr.name)), &attr.data_type).nullable(),
+            optional: false,
+        });
+    }
+
+    let doc = format!("`{}` composite type", composite.name);
+    Ok(interface_items(&to_struct_name(&composite.name), Some(&doc), &properties, config.typescript.zod))
+}
+
+fn table_items(
+    table: &TomlTable,
+    relations: &Relations,
+    struct_names: &StructNames,
+    types: &TsTypes,
+    config: &CodegenConfig,
+) -> Result<String> {
+    let options = model_options(config, &table.name);
+    let mut properties = column_properties(&table.name, &table.column, types, &options)?;
+
+    let relation = |doc: String, field: &str, table: &str, many: bool| -> Result<Property> {
+        let name = &struct_names[table];
+        let ts_type = if many {
+            TsType {
+                ts: format!("{}[]", name),
+                zod: format!("z.array(z.lazy(() => {}Schema))", name),
+            }
+        } else {
+            TsType {
+                ts: name.clone(),
+                zod: format!("z.lazy(() => {}Schema)", name),
+            }
+        };
+        Ok(Property {
+            doc: Some(doc),
+            name: options.serde_name(&field_name(field))?,
+            ts_type,
+            optional: true,
+        })
+    };
+
+    for r in relations.belongs_to.get(&table.name).into_iter().flatten() {
+        properties.push(relation(format!("belongs_to `{}`", r.table), &r.field, &r.table, false)?);
+    }
+    for r in relations.has_one.get(&table.name).into_iter().flatten() {
+        properties.push(relation(format!("has_one `{}`", r.table), &r.field, &r.table, false)?);
+    }
+    for r in relations.has_many.get(&table.name).into_iter().flatten() {
+        properties.push(relation(format!("has_many `{}`", r.table), &r.field, &r.table, true)?);
+    }
+    for r in relations.many_to_many.get(&table.name).into_iter().flatten() {
+        let doc = format!("many_to_many `{}` through `{}`", r.table, r.join_table);
+        properties.push(relation(doc, &r.field, &r.table, true)?);
+    }
+
+    Ok(interface_items(
+        &struct_names[&table.name],
+        table.comment.as_deref(),
+        &properties,
+        config.typescript.zod,
+    ))
+}
+
+fn view_items(view: &TomlView, name: &str, types: &TsTypes, config: &CodegenConfig) -> Result<String> {
+    let options = model_options(config, &view.name);
+    let properties = column_properties(&view.name, &view.column, types, &options)?;
+    let kind = if view.materialized { "materialized view" } else { "view" };
+    let doc = format!("Read-only row of the `{}` {}", view.name, kind);
+
+    Ok(interface_items(name, Some(&doc), &properties, config.typescript.zod))
+}
+
+fn column_properties(owner: &str, columns: &[Column], types: &TsTypes, options: &ModelOptions) -> Result<Vec<Property>> {
+    columns
+        .iter()
+        .map(|col| {
+            Ok(Property {
+                doc: col.comment.clone(),
+                name: options.serde_name(&field_name(&col.name))?,
+                ts_type: types.column_type(owner, col),
+                optional: false,
+            })
+        })
+        .collect()
+}
+
+/// An interface, followed by its zod schema when `zod` is set
+fn interface_items(name: &str, doc: Option<&str>, properties: &[Property], zod: bool) -> String {
+    let mut lines = doc.map(|doc| doc_lines(doc, "")).unwrap_or_default();
+    lines.push(format!("export interface {} {{", name));
+    for property in properties {
+        if let Some(doc) = &property.doc {
+            lines.extend(doc_lines(doc, "  "));
+        }
+        let optional = if property.optional { "?" } else { "" };
+        lines.push(format!("  {}{}: {};", property_key(&property.name), optional, property.ts_type.ts));
+    }
+    lines.push("}".to_string());
+
+    if zod {
+        lines.push(String::new());
+        lines.push(format!("export const {}Schema: z.ZodType<{}> = z.object({{", name, name));
+        for property in properties {
+            let optional = if property.optional { ".optional()" } else { "" };
+            lines.push(format!("  {}: {}{},", property_key(&property.name), property.ts_type.zod, optional));
+        }
+        lines.push("});".to_string());
+    }
+
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+/// A comment as a JSDoc block at the given indentation
+fn doc_lines(comment: &str, indent: &str) -> Vec<String> {
+    let comment = comment.replace("*/", "*\\/");
+    let lines: Vec<&str> = comment.lines().map(str::trim_end).collect();
+    if let [line] = lines.as_slice() {
+        return vec![format!("{}/** {} */", indent, line)];
+    }
+
+    let mut doc = vec![format!("{}/**", indent)];
+    for line in lines {
+        if line.trim().is_empty() {
+            doc.push(format!("{} *", indent));
+        } else {
+            doc.push(format!("{} * {}", indent, line));
+        }
+    }
+    doc.push(format!("{} */", indent));
+    doc
+}
+
+/// A property name, quoted unless it is a plain identifier
+fn property_key(name: &str) -> String {
+    let is_identifier = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier { name.to_string() } else { string_literal(name) }
+}
+
+fn string_literal(value: &str) -> String {
+    serde_json::Value::String(value.to_string()).to_string()
+}
@@ -1,7 +1,111 @@
 use anyhow::{Result, Context};
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::path::Path;
 
 pub fn retrieve_from_env(key: &str) -> Result<String> {
     dotenvy::dotenv().ok();
     env::var(key).with_context(|| format!("Missing environment variable: {}", key))
-}
\ No newline at end of file
+}
+
+/// Project settings read from `hive.toml`
+#[derive(Debug, Default, Deserialize)]
+pub struct ProjectConfig {
+    #[serde(default)]
+    pub codegen: CodegenConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CodegenConfig {
+    /// Derives added to every generated type, such as `serde::Serialize`
+    #[serde(default)]
+    pub derives: Vec<String>,
+    /// Attributes added to every generated type, such as
+    /// `#[serde(rename_all = "camelCase")]`
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// Derive `validator::Validate`, with rules from varchar lengths and
+    /// domain CHECK constraints
+    #[serde(default)]
+    pub validate: bool,
+    /// Wrap single-column primary keys in newtypes such as `UserId`, also
+    /// used by the foreign keys referencing them
+    #[serde(default)]
+    pub id_types: bool,
+    /// Rust type paths keyed by Postgres type (`timestamptz`) or by
+    /// `table.column`, taking precedence over the built-in mapping
+    #[serde(default)]
+    pub types: HashMap<String, String>,
+    /// Struct names keyed by table or view, replacing the singularized name
+    #[serde(default)]
+    pub structs: HashMap<String, String>,
+    /// Relation field names keyed by foreign key column as `table.column`
+    #[serde(default)]
+    pub relations: HashMap<String, RelationNames>,
+    /// Per-type settings keyed by table, view, domain or composite type
+    #[serde(default)]
+    pub models: HashMap<String, ModelConfig>,
+    /// Settings for `hive codegen --target typescript`
+    #[serde(default)]
+    pub typescript: TypeScriptConfig,
+    /// Settings for `hive codegen --target openapi`
+    #[serde(default)]
+    pub openapi: OpenApiConfig,
+}
+
+/// Settings for one generated type; each one set replaces the project-wide value
+#[derive(Debug, Default, Deserialize)]
+pub struct ModelConfig {
+    pub derives: Option<Vec<String>>,
+    pub attributes: Option<Vec<String>>,
+    pub validate: Option<bool>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TypeScriptConfig {
+    /// Emit a zod schema alongside each interface
+    #[serde(default)]
+    pub zod: bool,
+    /// TypeScript types keyed by Postgres type or `table.column`, needed
+    /// where `[codegen.types]` names a Rust type
+    #[serde(default)]
+    pub types: HashMap<String, String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct OpenApiConfig {
+    /// JSON Schemas keyed by Postgres type or `table.column`, needed where
+    /// `[codegen.types]` names a Rust type
+    #[serde(default)]
+    pub types: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RelationNames {
+    /// Field on the referencing table, `author` for `posts.author_id`
+    pub belongs_to: Option<String>,
+    /// Field on the referenced table. Defaults to the child table's plural,
+    /// prefixed with the belongs_to name when the child has several foreign
+    /// keys to that table: `author_posts` and `editor_posts` for
+    /// `posts.author_id` and `posts.editor_id`
+    pub has_many: Option<String>,
+    /// Field on the referenced table when this column is unique, such as `profile`
+    pub has_one: Option<String>,
+    /// Field reaching this column's table through a join table, such as
+    /// `labels` for `post_tags.tag_id`
+    pub many_to_many: Option<String>,
+}
+
+impl ProjectConfig {
+    /// Read the project config, falling back to defaults when the file does not exist
+    pub fn load(path: &str) -> Result<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Self::default());
+        }
+
+        let toml_str = fs::read_to_string(path)?;
+        toml::from_str(&toml_str).with_context(|| format!("Failed to parse {}", path))
+    }
+}
@@ -0,0 +1,350 @@
+use anyhow::{Context, Result};
+use toml_edit::{ArrayOfTables, DocumentMut, Item, Key, RawString, Table, Value};
+
+// ============ Canonical Layout ============
+
+/// Canonical key order for one kind of TOML table
+struct Layout {
+    /// Keys in canonical order; unknown keys follow in their original order
+    keys: &'static [&'static str],
+    /// Keys that array-of-tables entries are sorted by; empty keeps their order
+    sort_by: &'static [&'static str],
+    /// Layouts for nested tables and arrays of tables
+    children: &'static [(&'static str, &'static Layout)],
+    /// Layout for every table in a map keyed by name, sorted by name
+    named: Option<&'static Layout>,
+}
+
+impl Layout {
+    const fn keys(keys: &'static [&'static str]) -> Self {
+        Layout { keys, sort_by: &[], children: &[], named: None }
+    }
+
+    fn child(&self, key: &str) -> &'static Layout {
+        self.named
+            .or_else(|| self.children.iter().find(|(k, _)| *k == key).map(|(_, l)| *l))
+            .unwrap_or(&OPAQUE)
+    }
+}
+
+/// Tables whose keys are data, such as shorthand `columns`; only spacing changes
+const OPAQUE: Layout = Layout::keys(&[]);
+
+const COLUMN: Layout = Layout::keys(&["name", "data_type", "is_nullable", "default", "comment"]);
+
+const ROOT: Layout = Layout {
+    keys: &[
+        "extensions", "domain", "composite_type", "mixin", "table", "view", "function", "trigger",
+        "grant",
+    ],
+    sort_by: &[],
+    children: &[
+        ("domain", &Layout {
+            sort_by: &["name"],
+            ..Layout::keys(&["name", "base_type", "not_null", "default", "check"])
+        }),
+        ("composite_type", &Layout {
+            sort_by: &["name"],
+            children: &[("attribute", &Layout::keys(&["name", "data_type"]))],
+            ..Layout::keys(&["name", "attribute"])
+        }),
+        ("mixin", &Layout {
+            named: Some(&Layout {
+                children: &[("column", &COLUMN)],
+                ..Layout::keys(&["columns", "column"])
+            }),
+            ..Layout::keys(&[])
+        }),
+        ("table", &Layout { sort_by: &["name"], ..TABLE }),
+        ("view", &Layout {
+            sort_by: &["name"],
+            children: &[("column", &COLUMN)],
+            ..Layout::keys(&["name", "materialized", "definition", "column"])
+        }),
+        ("function", &Layout {
+            sort_by: &["name", "arguments"],
+            ..Layout::keys(&[
+                "name", "arguments", "returns", "language", "volatility", "strict",
+                "security_definer", "search_path", "body",
+            ])
+        }),
+        ("trigger", &Layout {
+            sort_by: &["table", "name"],
+            ..Layout::keys(&[
+                "name", "table", "timing", "events", "for_each", "function", "arguments", "when",
+            ])
+        }),
+        ("grant", &Layout::keys(&["role", "privileges", "tables", "sequences"])),
+    ],
+    named: None,
+};
+
+/// A table entry, which is also the root of a split schema's table file
+const TABLE: Layout = Layout {
+    keys: &[
+        "name", "comment", "rls_enabled", "force_rls", "partition_by", "include", "columns",
+        "column", "foreign_key", "index", "policy", "partition",
+    ],
+    sort_by: &[],
+    children: &[
+        ("partition_by", &Layout::keys(&["strategy", "columns"])),
+        ("column", &COLUMN),
+        ("foreign_key", &Layout {
+            sort_by: &["column", "referenced_table"],
+            ..Layout::keys(&["column", "referenced_table", "referenced_column"])
+        }),
+        ("index", &Layout {
+            sort_by: &["name"],
+            ..Layout::keys(&["name", "columns", "is_unique", "index_type"])
+        }),
+        ("policy", &Layout {
+            sort_by: &["name"],
+            ..Layout::keys(&["name", "command", "roles", "restrictive", "using", "with_check"])
+        }),
+        ("partition", &Layout { sort_by: &["name"], ..Layout::keys(&["name", "bound"]) }),
+    ],
+    named: None,
+};
+
+// ============ Formatting ============
+
+/// Rewrite a schema file in canonical form: known keys in struct order,
+/// named entries sorted, one blank line before each header and consistent
+/// spacing. Comments are kept with the item they precede. Column order is
+/// left alone since it decides the order of columns in CREATE TABLE.
+/// `single_table` files hold one table at the top level, as in a split
+/// schema's `tables/` directory.
+pub fn format_toml(text: &str, single_table: bool) -> Result<String> {
+    let (header, body) = split_header(text);
+    let mut doc: DocumentMut = body.parse().context("Failed to parse TOML")?;
+
+    let trailing = comment_lines(Some(doc.trailing()));
+    doc.set_trailing(trailing);
+
+    let layout = if single_table { &TABLE } else { &ROOT };
+    format_table(doc.as_table_mut(), layout);
+
+    let mut position = 0;
+    renumber_tables(doc.as_table_mut(), &mut position);
+
+    let formatted = doc.to_string();
+    let formatted = formatted.trim_matches('\n');
+    if header.is_empty() {
+        Ok(format!("{}\n", formatted))
+    } else {
+        Ok(format!("{}\n{}\n", header, formatted))
+    }
+}
+
+/// The file's leading comment, up to the last blank line before the first
+/// key or header, and the rest of the text with those lines blanked so
+/// parse errors keep their line numbers. The header stays at the top
+/// however entries are sorted; a comment directly above an entry moves
+/// with it.
+fn split_header(text: &str) -> (String, String) {
+    let mut header_end = 0;
+    let mut offset = 0;
+    let mut has_entry = false;
+    for line in text.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            header_end = offset + line.len();
+        } else if !trimmed.starts_with('#') {
+            has_entry = true;
+            break;
+        }
+        offset += line.len();
+    }
+    if !has_entry {
+        return (String::new(), text.to_string());
+    }
+
+    let (header, rest) = text.split_at(header_end);
+    let blanked = "\n".repeat(header.matches('\n').count());
+    (comment_lines(Some(&RawString::from(header))), format!("{}{}", blanked, rest))
+}
+
+fn format_table(table: &mut Table, layout: &Layout) {
+    let mut entries = Vec::new();
+    let keys: Vec<String> = table.iter().map(|(k, _)| k.to_string()).collect();
+    for key in keys {
+        if let Some(entry) = table.remove_entry(&key) {
+            entries.push(entry);
+        }
+    }
+
+    if layout.named.is_some() {
+        entries.sort_by(|(a, _), (b, _)| a.get().cmp(b.get()));
+    } else {
+        let rank = |key: &Key| {
+            layout.keys.iter().position(|k| *k == key.get()).unwrap_or(layout.keys.len())
+        };
+        entries.sort_by_key(|(key, _)| rank(key));
+    }
+
+    for (mut key, mut item) in entries {
+        let child = layout.child(key.get());
+        match &mut item {
+            Item::Value(value) => format_key_value(&mut key, value),
+            Item::Table(nested) => format_table(nested, child),
+            Item::ArrayOfTables(array) => format_array_of_tables(array, child),
+            Item::None => {}
+        }
+        table.insert_formatted(&key, item);
+    }
+
+    let decor = table.decor_mut();
+    let prefix = format!("\n{}", comment_lines(decor.prefix()));
+    let suffix = trailing_comment(decor.suffix());
+    decor.set_prefix(prefix);
+    decor.set_suffix(suffix);
+}
+
+fn format_array_of_tables(array: &mut ArrayOfTables, layout: &Layout) {
+    let mut tables: Vec<Table> = std::mem::take(array).into_iter().collect();
+
+    if !layout.sort_by.is_empty() {
+        let sort_key = |table: &Table| -> Vec<String> {
+            layout
+                .sort_by
+                .iter()
+                .map(|k| table.get(k).and_then(|v| v.as_str()).unwrap_or_default().to_string())
+                .collect()
+        };
+        tables.sort_by_cached_key(sort_key);
+    }
+
+    for mut table in tables {
+        format_table(&mut table, layout);
+        array.push(table);
+    }
+}
+
+/// `key = value` with comments above the key and after the value kept
+fn format_key_value(key: &mut Key, value: &mut Value) {
+    let decor = key.leaf_decor_mut();
+    let prefix = comment_lines(decor.prefix());
+    decor.set_prefix(prefix);
+    decor.set_suffix(" ");
+
+    let suffix = trailing_comment(value.decor().suffix());
+    match value {
+        Value::Array(array) if !array_has_comments(array) => array.fmt(),
+        Value::InlineTable(table) => table.fmt(),
+        _ => {}
+    }
+    value.decor_mut().set_prefix(" ");
+    value.decor_mut().set_suffix(suffix);
+}
+
+fn array_has_comments(array: &toml_edit::Array) -> bool {
+    let has_comment = |raw: Option<&RawString>| raw.and_then(|r| r.as_str()).is_some_and(|s| s.contains('#'));
+
+    has_comment(Some(array.trailing()))
+        || array.iter().any(|value| {
+            has_comment(value.decor().prefix())
+                || has_comment(value.decor().suffix())
+                || matches!(value, Value::Array(inner) if array_has_comments(inner))
+        })
+}
+
+/// Give every header a position in the order it now appears, since
+/// toml_edit prints tables by their original position
+fn renumber_tables(table: &mut Table, position: &mut isize) {
+    table.set_position(*position);
+    *position += 1;
+
+    for (_, item) in table.iter_mut() {
+        match item {
+            Item::Table(nested) => renumber_tables(nested, position),
+            Item::ArrayOfTables(array) => {
+                for nested in array.iter_mut() {
+                    renumber_tables(nested, position);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The comment lines of a decor prefix, without blank lines or indentation
+fn comment_lines(raw: Option<&RawString>) -> String {
+    raw.and_then(|r| r.as_str())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| line.starts_with('#'))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// A comment after a value or header on the same line
+fn trailing_comment(raw: Option<&RawString>) -> String {
+    match raw.and_then(|r| r.as_str()).map(str::trim) {
+        Some(comment) if comment.starts_with('#') => format!(" {}", comment),
+        _ => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(text: &str) -> String {
+        format_toml(text, false).unwrap()
+    }
+
+    #[test]
+    fn reorders_keys() {
+        let text = "[[table]]\ncolumns = { id = \"bigint\" }\ncomment = \"c\"\nname = \"users\"\n";
+        assert_eq!(
+            format(text),
+            "[[table]]\nname = \"users\"\ncomment = \"c\"\ncolumns = { id = \"bigint\" }\n"
+        );
+
+        // Unknown keys follow the known ones in their original order
+        let text = "[[domain]]\nzeta = 1\nbase_type = \"text\"\nalpha = 2\nname = \"d\"\n";
+        assert_eq!(format(text), "[[domain]]\nname = \"d\"\nbase_type = \"text\"\nzeta = 1\nalpha = 2\n");
+    }
+
+    #[test]
+    fn sorts_named_entries() {
+        let text = "[[table]]\nname = \"zebras\"\n\n[[table]]\nname = \"apes\"\n\n[mixin.timestamps]\ncolumns = {}\n\n[mixin.audit]\ncolumns = {}\n";
+        assert_eq!(
+            format(text),
+            "[mixin.audit]\ncolumns = {}\n\n[mixin.timestamps]\ncolumns = {}\n\n[[table]]\nname = \"apes\"\n\n[[table]]\nname = \"zebras\"\n"
+        );
+    }
+
+    #[test]
+    fn keeps_columns_in_order() {
+        let text = "[[table]]\nname = \"t\"\n\n[[table.column]]\nname = \"b\"\ndata_type = \"text\"\n\n[[table.column]]\nname = \"a\"\ndata_type = \"text\"\n";
+        assert_eq!(format(text), text);
+    }
+
+    #[test]
+    fn keeps_comments_with_their_items() {
+        let text = "# schema header\n\n[[table]]\nname = \"zebras\" # striped\n\n# people\n[[table]]\n# the name\nname = \"apes\"\n";
+        assert_eq!(
+            format(text),
+            "# schema header\n\n# people\n[[table]]\n# the name\nname = \"apes\"\n\n[[table]]\nname = \"zebras\" # striped\n"
+        );
+
+        // Without a blank line the comment belongs to the first entry
+        let text = "# zebras\n[[table]]\nname = \"zebras\"\n\n[[table]]\nname = \"apes\"\n";
+        assert_eq!(format(text), "[[table]]\nname = \"apes\"\n\n# zebras\n[[table]]\nname = \"zebras\"\n");
+    }
+
+    #[test]
+    fn idempotent() {
+        let text = "# header\n\n[[table]]\ncolumns = { id = \"bigint primary key\" }\nname = \"b\"\n[[table]]\nname = \"a\"\n\n\n[[table.index]]\nis_unique = true\nname = \"a_key\"\n";
+        let once = format(text);
+        assert_eq!(format(&once), once);
+    }
+
+    #[test]
+    fn parse_errors_keep_line_numbers() {
+        let error = format_toml("# header\n\n[[table]]\nname = \n", false).unwrap_err();
+        assert!(format!("{:#}", error).contains("line 4"), "{:#}", error);
+    }
+}
@@ -0,0 +1,142 @@
+//! English inflection for generated names. Only the last word of a
+//! snake_case name is inflected, so `order_items` becomes `order_item`.
+
+/// Plural form; names that are already plural are returned unchanged
+pub(crate) fn pluralize(name: &str) -> String {
+    let (prefix, word) = split_last_word(name);
+    if !is_inflectable(word) || singular_word(word) != word {
+        return name.to_string();
+    }
+    format!("{}{}", prefix, plural_word(word))
+}
+
+/// Singular form; names that are already singular are returned unchanged
+pub(crate) fn singularize(name: &str) -> String {
+    let (prefix, word) = split_last_word(name);
+    if !is_inflectable(word) {
+        return name.to_string();
+    }
+    format!("{}{}", prefix, singular_word(word))
+}
+
+fn split_last_word(name: &str) -> (&str, &str) {
+    match name.rfind('_') {
+        Some(i) => name.split_at(i + 1),
+        None => ("", name),
+    }
+}
+
+/// The suffix rules work on ASCII words only
+fn is_inflectable(word: &str) -> bool {
+    !word.is_empty() && word.is_ascii() && !UNCOUNTABLE.contains(&word.to_lowercase().as_str())
+}
+
+fn plural_word(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if let Some((_, plural)) = IRREGULAR.iter().find(|(singular, _)| *singular == lower) {
+        return with_case_of(word, plural);
+    }
+
+    if let Some(stem) = lower.strip_suffix("sis") {
+        return format!("{}ses", &word[..stem.len()]);
+    }
+    if lower.ends_with("quiz") {
+        return format!("{}zes", word);
+    }
+    if ["s", "x", "z", "ch", "sh"].iter().any(|suffix| lower.ends_with(suffix)) {
+        return format!("{}es", word);
+    }
+    if let Some(stem) = lower.strip_suffix('y')
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        return format!("{}ies", &word[..stem.len()]);
+    }
+
+    format!("{}s", word)
+}
+
+fn singular_word(word: &str) -> String {
+    let lower = word.to_lowercase();
+    if IRREGULAR.iter().any(|(singular, _)| *singular == lower) {
+        return word.to_string();
+    }
+    if let Some((singular, _)) = IRREGULAR.iter().find(|(_, plural)| *plural == lower) {
+        return with_case_of(word, singular);
+    }
+
+    let keep = |len: usize| word[..len].to_string();
+
+    if let Some(stem) = lower.strip_suffix("ses")
+        && ANALYSIS_STEMS.iter().any(|s| stem.ends_with(s))
+    {
+        return format!("{}sis", keep(stem.len()));
+    }
+    if lower.ends_with("quizzes") {
+        return keep(lower.len() - 3);
+    }
+    for suffix in ["statuses", "aliases", "buses"] {
+        if lower.ends_with(suffix) {
+            return keep(lower.len() - 2);
+        }
+    }
+    if let Some(stem) = lower.strip_suffix("ies")
+        && stem.len() > 1
+        && !stem.ends_with(['a', 'e', 'i', 'o', 'u'])
+    {
+        return format!("{}y", keep(stem.len()));
+    }
+    for suffix in ["sses", "shes", "ches", "xes", "zzes"] {
+        if lower.ends_with(suffix) {
+            return keep(lower.len() - 2);
+        }
+    }
+    if lower.ends_with('s') && !["ss", "us", "is"].iter().any(|suffix| lower.ends_with(suffix)) {
+        return keep(lower.len() - 1);
+    }
+
+    word.to_string()
+}
+
+/// `replacement` with the first letter capitalized when `word`'s is
+fn with_case_of(word: &str, replacement: &str) -> String {
+    if word.starts_with(|c: char| c.is_uppercase()) {
+        let mut chars = replacement.chars();
+        chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+    } else {
+        replacement.to_string()
+    }
+}
+
+const UNCOUNTABLE: &[&str] = &[
+    "audio", "data", "equipment", "feedback", "fish", "information", "metadata", "money",
+    "news", "police", "rice", "series", "sheep", "software", "species", "staff", "deer",
+];
+
+const IRREGULAR: &[(&str, &str)] = &[
+    ("person", "people"),
+    ("man", "men"),
+    ("woman", "women"),
+    ("child", "children"),
+    ("mouse", "mice"),
+    ("goose", "geese"),
+    ("foot", "feet"),
+    ("tooth", "teeth"),
+    ("ox", "oxen"),
+    ("leaf", "leaves"),
+    ("life", "lives"),
+    ("knife", "knives"),
+    ("wife", "wives"),
+    ("half", "halves"),
+    ("shelf", "shelves"),
+    ("wolf", "wolves"),
+    ("criterion", "criteria"),
+    ("datum", "data"),
+    ("medium", "media"),
+    ("cache", "caches"),
+    ("movie", "movies"),
+    ("cookie", "cookies"),
+    ("zombie", "zombies"),
+];
+
+/// `analyses`, `crises`, `theses` and the like end in `sis` when singular
+const ANALYSIS_STEMS: &[&str] = &["analy", "cri", "diagno", "parenthe", "progno", "synop", "the"];
@@ -1,9 +1,22 @@
+mod codegen;
 mod connect;
 mod config;
+mod format;
+mod inflect;
 mod schema;
 mod toml_schema;
+mod validate;
 
 pub use connect::{connect, DbPool};
-pub use config::retrieve_from_env;
-pub use schema::{Schema, Table, Column, ForeignKey, Index};
-pub use toml_schema::TomlSchema;
+pub use codegen::CodegenTarget;
+pub use config::{
+    retrieve_from_env, CodegenConfig, ModelConfig, OpenApiConfig, ProjectConfig, RelationNames,
+    TypeScriptConfig,
+};
+pub use format::format_toml;
+pub use schema::{
+    Schema, Table, View, Column, ForeignKey, Index, PartitionBy, Partition, Policy, Domain,
+    CompositeType, CompositeAttribute, Function, Trigger, Grant,
+};
+pub use toml_schema::{TomlSchema, TomlTable};
+pub use validate::{Diagnostic, Severity, SourceLocation, SourceMap};
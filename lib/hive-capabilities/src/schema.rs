@@ -1,17 +1,28 @@
 use anyhow::{Context, Result};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use sqlx::{PgPool, Row};
-use std::collections::{HashMap, HashSet};
-use std::fs;
-use std::path::Path;
+use sqlx::postgres::PgRow;
+use sqlx::{PgConnection, PgPool, Row};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 
-use crate::toml_schema::{TomlSchema, TomlTable};
+use crate::toml_schema::TomlSchema;
+use crate::validate::SourceMap;
 
 // ============ Type Definitions ============
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct Schema {
+    pub extensions: Vec<String>,
+    pub domains: Vec<Domain>,
+    pub composite_types: Vec<CompositeType>,
     pub tables: HashMap<String, Table>,
+    pub views: HashMap<String, View>,
+    pub functions: Vec<Function>,
+    pub triggers: Vec<Trigger>,
+    pub grants: Vec<Grant>,
+    /// Where each item was defined, when read from TOML
+    #[serde(skip)]
+    pub source_map: SourceMap,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -19,24 +30,38 @@ pub struct Table {
     pub columns: Vec<Column>,
     pub foreign_keys: Vec<ForeignKey>,
     pub indexes: Vec<Index>,
+    pub comment: Option<String>,
+    pub rls_enabled: bool,
+    pub force_rls: bool,
+    pub policies: Vec<Policy>,
+    pub partition_by: Option<PartitionBy>,
+    pub partitions: Vec<Partition>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
+pub struct View {
+    pub definition: String,
+    pub materialized: bool,
+    pub columns: Vec<Column>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Column {
     pub name: String,
     pub data_type: String,
     pub is_nullable: bool,
     pub default: Option<String>,
+    pub comment: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ForeignKey {
     pub column: String,
     pub referenced_table: String,
     pub referenced_column: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Index {
     pub name: String,
     pub columns: Vec<String>,
@@ -44,6 +69,133 @@ pub struct Index {
     pub index_type: String,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PartitionBy {
+    /// range, list or hash
+    pub strategy: String,
+    pub columns: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Partition {
+    pub name: String,
+    /// `FOR VALUES ...` clause, or `DEFAULT`
+    pub bound: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Policy {
+    pub name: String,
+    /// ALL, SELECT, INSERT, UPDATE or DELETE
+    #[serde(default = "default_policy_command")]
+    pub command: String,
+    #[serde(default = "default_policy_roles")]
+    pub roles: Vec<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub restrictive: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub using: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub with_check: Option<String>,
+}
+
+fn default_policy_command() -> String {
+    "ALL".to_string()
+}
+
+fn default_policy_roles() -> Vec<String> {
+    vec!["public".to_string()]
+}
+
+/// Privileges held by a role on tables (views included) and sequences.
+/// `"*"` in `tables` or `sequences` stands for every one in the schema.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Grant {
+    pub role: String,
+    pub privileges: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tables: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sequences: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Domain {
+    pub name: String,
+    pub base_type: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not_null: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<String>,
+    /// Boolean expression over VALUE
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
+    /// Constraints the check was joined from, when read from a database
+    #[serde(skip)]
+    pub check_constraints: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CompositeType {
+    pub name: String,
+    pub attribute: Vec<CompositeAttribute>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CompositeAttribute {
+    pub name: String,
+    pub data_type: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Function {
+    pub name: String,
+    /// Argument list as written inside the parentheses, defaults included
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub arguments: String,
+    pub returns: String,
+    pub language: String,
+    /// IMMUTABLE, STABLE or VOLATILE
+    #[serde(default = "default_volatility")]
+    pub volatility: String,
+    /// Return NULL without running the body when any argument is NULL
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub strict: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub security_definer: bool,
+    /// search_path set while the function runs, such as `public, pg_temp`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search_path: Option<String>,
+    pub body: String,
+}
+
+fn default_volatility() -> String {
+    "VOLATILE".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Trigger {
+    pub name: String,
+    pub table: String,
+    /// BEFORE, AFTER or INSTEAD OF
+    pub timing: String,
+    /// Any of INSERT, UPDATE, DELETE, TRUNCATE
+    pub events: Vec<String>,
+    /// ROW or STATEMENT
+    #[serde(default = "default_for_each")]
+    pub for_each: String,
+    pub function: String,
+    /// String arguments passed to the function as TG_ARGV
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<String>,
+}
+
+fn default_for_each() -> String {
+    "ROW".to_string()
+}
+
 // ============ Schema Methods ============
 
 impl Schema {
@@ -51,18 +203,33 @@ impl Schema {
     pub async fn from_db(pool: &PgPool) -> Result<Self> {
         let mut tables: HashMap<String, Table> = HashMap::new();
 
-        // Step 1: Get all columns
+        // Step 1: Get all columns (views are read separately below, and
+        // partitions are folded into their parent in step 2)
         let column_rows = sqlx::query(
             r#"
             SELECT
-                table_name,
-                column_name,
-                data_type,
-                is_nullable,
-                column_default
-            FROM information_schema.columns
-            WHERE table_schema = 'public'
-            ORDER BY table_name, ordinal_position
+                c.table_name,
+                c.column_name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                c.is_nullable,
+                c.column_default,
+                col_description(r.oid, c.ordinal_position::int) AS column_comment,
+                obj_description(r.oid, 'pg_class') AS table_comment,
+                r.relrowsecurity AS rls_enabled,
+                r.relforcerowsecurity AS force_rls
+            FROM information_schema.columns c
+            JOIN pg_class r
+                ON r.oid = format('%I.%I', c.table_schema, c.table_name)::regclass
+            JOIN pg_attribute a
+                ON a.attrelid = r.oid
+                AND a.attname = c.column_name
+            JOIN information_schema.tables t
+                ON t.table_schema = c.table_schema
+                AND t.table_name = c.table_name
+            WHERE c.table_schema = 'public'
+                AND t.table_type = 'BASE TABLE'
+                AND NOT r.relispartition
+            ORDER BY c.table_name, c.ordinal_position
             "#,
         )
         .fetch_all(pool)
@@ -75,6 +242,12 @@ impl Schema {
                 columns: Vec::new(),
                 foreign_keys: Vec::new(),
                 indexes: Vec::new(),
+                comment: row.get("table_comment"),
+                rls_enabled: row.get("rls_enabled"),
+                force_rls: row.get("force_rls"),
+                policies: Vec::new(),
+                partition_by: None,
+                partitions: Vec::new(),
             });
 
             table.columns.push(Column {
@@ -82,10 +255,64 @@ impl Schema {
                 data_type: row.get("data_type"),
                 is_nullable: row.get::<String, _>("is_nullable") == "YES",
                 default: row.get("column_default"),
+                comment: row.get("column_comment"),
             });
         }
 
-        // Step 2: Get foreign keys
+        // Step 2: Get partition keys and partitions
+        let partition_key_rows = sqlx::query(
+            r#"
+            SELECT
+                c.relname AS table_name,
+                pg_get_partkeydef(c.oid) AS partition_key
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = 'public'
+                AND c.relkind = 'p'
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for row in partition_key_rows {
+            let table_name: String = row.get("table_name");
+            let partition_key: String = row.get("partition_key");
+
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.partition_by = parse_partition_key(&partition_key);
+            }
+        }
+
+        let partition_rows = sqlx::query(
+            r#"
+            SELECT
+                parent.relname AS table_name,
+                child.relname AS partition_name,
+                pg_get_expr(child.relpartbound, child.oid) AS bound
+            FROM pg_inherits i
+            JOIN pg_class parent ON parent.oid = i.inhparent
+            JOIN pg_class child ON child.oid = i.inhrelid
+            JOIN pg_namespace n ON n.oid = parent.relnamespace
+            WHERE n.nspname = 'public'
+                AND child.relispartition
+            ORDER BY parent.relname, child.relname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for row in partition_rows {
+            let table_name: String = row.get("table_name");
+
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.partitions.push(Partition {
+                    name: row.get("partition_name"),
+                    bound: row.get("bound"),
+                });
+            }
+        }
+
+        // Step 3: Get foreign keys
         let fk_rows = sqlx::query(
             r#"
             SELECT
@@ -120,7 +347,7 @@ impl Schema {
             }
         }
 
-        // Step 3: Get indexes
+        // Step 4: Get indexes
         let index_rows = sqlx::query(
             r#"
             SELECT
@@ -136,7 +363,7 @@ impl Schema {
             JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = ANY(ix.indkey)
             JOIN pg_namespace n ON n.oid = t.relnamespace
             WHERE n.nspname = 'public'
-                AND t.relkind = 'r'
+                AND t.relkind IN ('r', 'p')
             ORDER BY t.relname, i.relname, a.attnum
             "#,
         )
@@ -172,13 +399,351 @@ impl Schema {
             }
         }
 
-        Ok(Schema { tables })
+        // Step 5: Get row-level security policies
+        let policy_rows = sqlx::query(
+            r#"
+            SELECT
+                tablename,
+                policyname,
+                cmd,
+                roles::text[] AS roles,
+                permissive = 'RESTRICTIVE' AS restrictive,
+                qual,
+                with_check
+            FROM pg_policies
+            WHERE schemaname = 'public'
+            ORDER BY tablename, policyname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for row in policy_rows {
+            let table_name: String = row.get("tablename");
+
+            if let Some(table) = tables.get_mut(&table_name) {
+                table.policies.push(Policy {
+                    name: row.get("policyname"),
+                    command: row.get("cmd"),
+                    roles: row.get("roles"),
+                    restrictive: row.get("restrictive"),
+                    using: row.get("qual"),
+                    with_check: row.get("with_check"),
+                });
+            }
+        }
+
+        // Step 6: Get views and materialized views
+        let view_rows = sqlx::query(
+            r#"
+            SELECT
+                c.relname AS view_name,
+                c.relkind = 'm' AS materialized,
+                pg_get_viewdef(c.oid, true) AS definition
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            WHERE n.nspname = 'public'
+                AND c.relkind IN ('v', 'm')
+            ORDER BY c.relname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut views: HashMap<String, View> = HashMap::new();
+
+        for row in view_rows {
+            let definition: String = row.get("definition");
+            views.insert(
+                row.get("view_name"),
+                View {
+                    definition: definition.trim().trim_end_matches(';').to_string(),
+                    materialized: row.get("materialized"),
+                    columns: Vec::new(),
+                },
+            );
+        }
+
+        // Step 7: Get view columns (pg_attribute also covers materialized views,
+        // which information_schema.columns leaves out)
+        let view_column_rows = sqlx::query(
+            r#"
+            SELECT
+                c.relname AS view_name,
+                a.attname AS column_name,
+                format_type(a.atttypid, NULL) AS data_type,
+                NOT a.attnotnull AS is_nullable,
+                col_description(c.oid, a.attnum) AS column_comment
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_attribute a ON a.attrelid = c.oid
+            WHERE n.nspname = 'public'
+                AND c.relkind IN ('v', 'm')
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            ORDER BY c.relname, a.attnum
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        for row in view_column_rows {
+            let view_name: String = row.get("view_name");
+
+            if let Some(view) = views.get_mut(&view_name) {
+                view.columns.push(Column {
+                    name: row.get("column_name"),
+                    data_type: row.get("data_type"),
+                    is_nullable: row.get("is_nullable"),
+                    default: None,
+                    comment: row.get("column_comment"),
+                });
+            }
+        }
+
+        // Step 8: Get functions (skipping ones owned by extensions)
+        let function_rows = sqlx::query(
+            r#"
+            SELECT
+                p.proname AS function_name,
+                pg_get_function_arguments(p.oid) AS arguments,
+                pg_get_function_result(p.oid) AS returns,
+                l.lanname AS language,
+                CASE p.provolatile
+                    WHEN 'i' THEN 'IMMUTABLE'
+                    WHEN 's' THEN 'STABLE'
+                    ELSE 'VOLATILE'
+                END AS volatility,
+                p.proisstrict AS strict,
+                p.prosecdef AS security_definer,
+                (
+                    SELECT substr(setting, length('search_path=') + 1)
+                    FROM unnest(p.proconfig) setting
+                    WHERE setting LIKE 'search\_path=%'
+                ) AS search_path,
+                p.prosrc AS body
+            FROM pg_proc p
+            JOIN pg_namespace n ON n.oid = p.pronamespace
+            JOIN pg_language l ON l.oid = p.prolang
+            WHERE n.nspname = 'public'
+                AND p.prokind = 'f'
+                AND NOT EXISTS (
+                    SELECT 1 FROM pg_depend d
+                    WHERE d.classid = 'pg_proc'::regclass
+                        AND d.objid = p.oid
+                        AND d.deptype = 'e'
+                )
+            ORDER BY p.proname, arguments
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let functions = function_rows
+            .into_iter()
+            .map(|row| {
+                let body: String = row.get("body");
+                Function {
+                    name: row.get("function_name"),
+                    arguments: row.get("arguments"),
+                    returns: row.get("returns"),
+                    language: row.get("language"),
+                    volatility: row.get("volatility"),
+                    strict: row.get("strict"),
+                    security_definer: row.get("security_definer"),
+                    search_path: row.get("search_path"),
+                    body: body.trim().to_string(),
+                }
+            })
+            .collect();
+
+        // Step 9: Get triggers
+        let trigger_rows = sqlx::query(
+            r#"
+            SELECT
+                t.tgname AS trigger_name,
+                c.relname AS table_name,
+                t.tgtype::int4 AS trigger_type,
+                p.proname AS function_name,
+                pg_get_triggerdef(t.oid) AS definition
+            FROM pg_trigger t
+            JOIN pg_class c ON c.oid = t.tgrelid
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            JOIN pg_proc p ON p.oid = t.tgfoid
+            WHERE n.nspname = 'public'
+                AND NOT t.tgisinternal
+            ORDER BY c.relname, t.tgname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let triggers = trigger_rows
+            .into_iter()
+            .map(|row| {
+                let trigger_type: i32 = row.get("trigger_type");
+                let definition: String = row.get("definition");
+                trigger_from_pg(
+                    row.get("trigger_name"),
+                    row.get("table_name"),
+                    trigger_type,
+                    row.get("function_name"),
+                    &definition,
+                )
+            })
+            .collect();
+
+        // Step 10: Get installed extensions (plpgsql ships with every database)
+        let extension_rows = sqlx::query(
+            r#"
+            SELECT extname
+            FROM pg_extension
+            WHERE extname <> 'plpgsql'
+            ORDER BY extname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let extensions = extension_rows.iter().map(|row| row.get("extname")).collect();
+
+        // Step 11: Get domains with their CHECK constraints
+        let domain_rows = sqlx::query(
+            r#"
+            SELECT
+                t.typname AS domain_name,
+                format_type(t.typbasetype, t.typtypmod) AS base_type,
+                t.typnotnull AS not_null,
+                t.typdefault AS domain_default,
+                ARRAY(
+                    SELECT pg_get_constraintdef(con.oid)
+                    FROM pg_constraint con
+                    WHERE con.contypid = t.oid AND con.contype = 'c'
+                    ORDER BY con.conname
+                ) AS checks,
+                ARRAY(
+                    SELECT con.conname::text
+                    FROM pg_constraint con
+                    WHERE con.contypid = t.oid AND con.contype = 'c'
+                    ORDER BY con.conname
+                ) AS check_constraints
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            WHERE n.nspname = 'public'
+                AND t.typtype = 'd'
+            ORDER BY t.typname
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let domains = domain_rows
+            .into_iter()
+            .map(|row| {
+                let checks: Vec<String> = row.get("checks");
+                Domain {
+                    name: row.get("domain_name"),
+                    base_type: row.get("base_type"),
+                    not_null: row.get("not_null"),
+                    default: row.get("domain_default"),
+                    check: joined_checks(&checks),
+                    check_constraints: row.get("check_constraints"),
+                }
+            })
+            .collect();
+
+        // Step 12: Get composite types and their attributes
+        let composite_rows = sqlx::query(
+            r#"
+            SELECT
+                t.typname AS type_name,
+                a.attname AS attribute_name,
+                format_type(a.atttypid, a.atttypmod) AS data_type
+            FROM pg_type t
+            JOIN pg_namespace n ON n.oid = t.typnamespace
+            JOIN pg_class c ON c.oid = t.typrelid
+            JOIN pg_attribute a ON a.attrelid = c.oid
+            WHERE n.nspname = 'public'
+                AND t.typtype = 'c'
+                AND c.relkind = 'c'
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            ORDER BY t.typname, a.attnum
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let mut composite_types: Vec<CompositeType> = Vec::new();
+
+        for row in composite_rows {
+            let type_name: String = row.get("type_name");
+            let attribute = CompositeAttribute {
+                name: row.get("attribute_name"),
+                data_type: row.get("data_type"),
+            };
+
+            match composite_types.last_mut() {
+                Some(composite) if composite.name == type_name => composite.attribute.push(attribute),
+                _ => composite_types.push(CompositeType {
+                    name: type_name,
+                    attribute: vec![attribute],
+                }),
+            }
+        }
+
+        // Step 13: Get privileges granted to roles other than the owner.
+        // aclexplode sees every grant, whereas information_schema only lists
+        // those involving the current user's roles
+        let grant_rows = sqlx::query(
+            r#"
+            SELECT
+                c.relname AS object_name,
+                c.relkind = 'S' AS is_sequence,
+                CASE WHEN a.grantee = 0 THEN 'public' ELSE pg_get_userbyid(a.grantee) END AS role_name,
+                a.privilege_type
+            FROM pg_class c
+            JOIN pg_namespace n ON n.oid = c.relnamespace
+            CROSS JOIN LATERAL aclexplode(c.relacl) a
+            WHERE n.nspname = 'public'
+                AND c.relkind IN ('r', 'p', 'v', 'm', 'S')
+                AND NOT c.relispartition
+                AND a.grantee <> c.relowner
+            ORDER BY role_name, c.relname, a.privilege_type
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let privilege_set: BTreeSet<GrantedPrivilege> = grant_rows
+            .iter()
+            .map(|row| GrantedPrivilege {
+                role: row.get("role_name"),
+                on_sequence: row.get("is_sequence"),
+                object: row.get("object_name"),
+                privilege: row.get("privilege_type"),
+            })
+            .collect();
+        let grants = group_privileges(&privilege_set);
+
+        Ok(Schema {
+            extensions,
+            domains,
+            composite_types,
+            tables,
+            views,
+            functions,
+            triggers,
+            grants,
+            source_map: SourceMap::default(),
+        })
     }
 
     /// Apply this schema to a database, generating and executing migrations
     pub async fn apply_to_db(&self, pool: &PgPool) -> Result<Vec<String>> {
         let current = Schema::from_db(pool).await?;
-        let migrations = generate_migrations(&current, self);
+        let target = deparse_with_db(self, pool).await?;
+        let migrations = generate_migrations(&current, &target);
 
         if migrations.is_empty() {
             println!("Database is already in sync with schema.");
@@ -201,27 +766,57 @@ impl Schema {
         Ok(migrations)
     }
 
-    pub fn from_toml_schema(toml_schema: TomlSchema) -> Self {
+    pub fn from_toml_schema(toml_schema: TomlSchema) -> Result<Self> {
         let mut tables = HashMap::new();
 
-        for toml_table in toml_schema.table {
+        for mut toml_table in toml_schema.table {
+            toml_table.expand(&toml_schema.mixin)?;
             tables.insert(
                 toml_table.name,
                 Table {
                     columns: toml_table.column,
                     foreign_keys: toml_table.foreign_key,
                     indexes: toml_table.index,
+                    comment: toml_table.comment,
+                    rls_enabled: toml_table.rls_enabled,
+                    force_rls: toml_table.force_rls,
+                    policies: toml_table.policy,
+                    partition_by: toml_table.partition_by,
+                    partitions: toml_table.partition,
+                },
+            );
+        }
+
+        let mut views = HashMap::new();
+
+        for toml_view in toml_schema.view {
+            views.insert(
+                toml_view.name,
+                View {
+                    definition: toml_view.definition,
+                    materialized: toml_view.materialized,
+                    columns: toml_view.column,
                 },
             );
         }
 
-        Schema { tables }
+        Ok(Schema {
+            extensions: toml_schema.extensions,
+            domains: toml_schema.domain,
+            composite_types: toml_schema.composite_type,
+            tables,
+            views,
+            functions: toml_schema.function,
+            triggers: toml_schema.trigger,
+            grants: toml_schema.grant,
+            source_map: toml_schema.source_map,
+        })
     }
 
-    /// Read schema from a TOML file
+    /// Read schema from a TOML file or split schema directory
     pub fn from_toml_file(path: &str) -> Result<Self> {
-        let toml_schema = TomlSchema::from_file(path)?;
-        Ok(Self::from_toml_schema(toml_schema))
+        let toml_schema = TomlSchema::from_path(path)?;
+        Self::from_toml_schema(toml_schema)
     }
 
     /// Write this schema to a TOML file
@@ -230,44 +825,273 @@ impl Schema {
         toml_schema.write_file(path)
     }
 
-    /// Generate model files from this schema
-    pub fn write_models(&self, output_path: &str) -> Result<()> {
-        // Ensure output directory exists
-        fs::create_dir_all(output_path)?;
-
-        // Convert to TomlSchema for easier iteration (preserves table structure)
+    /// Write this schema as a split directory with one file per table
+    pub fn write_toml_dir(&self, dir: &str) -> Result<()> {
         let toml_schema = TomlSchema::from_schema(self.clone());
+        toml_schema.write_dir(dir)
+    }
+}
+
+// ============ Migration Logic (private helpers) ============
+
+/// Rewrite the target's SQL the way Postgres deparses it, so it compares
+/// equal to the introspected text however it was written. Each snippet is
+/// created in a scratch schema and read back, all inside a transaction that
+/// is rolled back. Snippets Postgres rejects for now, such as a view over a
+/// new table, are kept as written, and so is everything when the role may
+/// not create the scratch schema.
+async fn deparse_with_db(target: &Schema, pool: &PgPool) -> Result<Schema> {
+    let mut deparsed = target.clone();
+    let mut tx = pool.begin().await?;
+
+    // Named after the transaction, so concurrent runs don't collide
+    let scratch: String = sqlx::query_scalar("SELECT 'hive_scratch_' || txid_current()")
+        .fetch_one(&mut *tx)
+        .await?;
+    if !scratch_execute(&mut tx, &format!("CREATE SCHEMA \"{}\"", scratch)).await? {
+        println!("warning: Can't create a scratch schema to deparse SQL in, so SQL is compared as written");
+        tx.rollback().await?;
+        return Ok(deparsed);
+    }
+    // Views built on other views should see the new definitions
+    sqlx::query(&format!("SET LOCAL search_path = \"{}\", public", scratch))
+        .execute(&mut *tx)
+        .await?;
+
+    let view_names: Vec<String> = target.views.keys().cloned().collect();
+    for name in order_views_by_dependency(&view_names, target) {
+        let view = deparsed.views.get_mut(&name).expect("ordered views come from the target");
+        let create = format!(
+            "CREATE VIEW \"{}\".\"{}\" AS {}",
+            scratch,
+            name,
+            view.definition.trim().trim_end_matches(';')
+        );
+        let query = format!(
+            "SELECT pg_get_viewdef('\"{}\".\"{}\"'::regclass, true)",
+            scratch, name
+        );
+        if let Some(row) = scratch_deparse(&mut tx, &create, &query).await? {
+            let definition: String = row.get(0);
+            view.definition = definition.trim().trim_end_matches(';').to_string();
+        }
+    }
+
+    // Domain defaults and checks, which introspection joins with AND
+    for (i, domain) in deparsed.domains.iter_mut().enumerate() {
+        let scratch_name = format!("\"{}\".\"domain_{}\"", scratch, i);
+        let create = generate_create_domain(&scratch_name, domain);
+        let query = format!(
+            r#"
+            SELECT
+                t.typdefault AS domain_default,
+                ARRAY(
+                    SELECT pg_get_constraintdef(con.oid)
+                    FROM pg_constraint con
+                    WHERE con.contypid = t.oid AND con.contype = 'c'
+                ) AS checks
+            FROM pg_type t
+            WHERE t.oid = '{}'::regtype
+            "#,
+            scratch_name
+        );
+        if let Some(row) = scratch_deparse(&mut tx, &create, &query).await? {
+            let checks: Vec<String> = row.get("checks");
+            domain.default = row.get("domain_default");
+            domain.check = joined_checks(&checks);
+        }
+    }
+
+    // Argument lists, return types and settings come back in catalog form
+    for (i, function) in deparsed.functions.iter_mut().enumerate() {
+        let scratch_name = format!("\"{}\".\"function_{}\"", scratch, i);
+        let create = generate_create_function(&scratch_name, function);
+        let query = format!(
+            r#"
+            SELECT
+                pg_get_function_arguments(p.oid) AS arguments,
+                pg_get_function_result(p.oid) AS returns,
+                (
+                    SELECT substr(setting, length('search_path=') + 1)
+                    FROM unnest(p.proconfig) setting
+                    WHERE setting LIKE 'search\_path=%'
+                ) AS search_path
+            FROM pg_proc p
+            WHERE p.pronamespace = '"{}"'::regnamespace
+                AND p.proname = 'function_{}'
+            "#,
+            scratch, i
+        );
+        if let Some(row) = scratch_deparse(&mut tx, &create, &query).await? {
+            function.arguments = row.get("arguments");
+            function.returns = row.get("returns");
+            function.search_path = row.get("search_path");
+        }
+    }
 
-        // Build relation maps
-        let (belongs_to, has_many) = build_relation_maps(&toml_schema);
+    // Policy expressions, partition bounds and trigger conditions, on
+    // scratch copies of the tables that shadow the real ones through the
+    // search_path
+    let mut scratch_tables = HashSet::new();
+    for (name, table) in deparsed.tables.iter_mut() {
+        if !scratch_execute(&mut tx, &generate_create_table(name, table)).await? {
+            continue;
+        }
+        scratch_tables.insert(name.clone());
 
-        // Generate each table file
-        let mut table_names: Vec<&str> = toml_schema.table.iter().map(|t| t.name.as_str()).collect();
-        table_names.sort();
+        let column_rows = sqlx::query(&format!(
+            r#"
+            SELECT
+                a.attname AS column_name,
+                format_type(a.atttypid, a.atttypmod) AS data_type,
+                pg_get_expr(d.adbin, d.adrelid) AS column_default
+            FROM pg_attribute a
+            LEFT JOIN pg_attrdef d ON d.adrelid = a.attrelid AND d.adnum = a.attnum
+            WHERE a.attrelid = '"{}"."{}"'::regclass
+                AND a.attnum > 0
+                AND NOT a.attisdropped
+            "#,
+            scratch, name
+        ))
+        .fetch_all(&mut *tx)
+        .await?;
 
-        for table in &toml_schema.table {
-            let file_content = generate_table_file(table, &belongs_to, &has_many, &table_names);
-            let file_path = Path::new(output_path).join(format!("{}.rs", table.name));
-            fs::write(&file_path, file_content)?;
+        for row in column_rows {
+            let column_name: String = row.get("column_name");
+            if let Some(column) = table.columns.iter_mut().find(|c| c.name == column_name) {
+                column.data_type = row.get("data_type");
+                // Defaults CREATE TABLE leaves out are kept as written
+                if let Some(default) = row.get::<Option<String>, _>("column_default") {
+                    column.default = Some(default);
+                }
+            }
         }
 
-        // Generate mod.rs
-        let mod_content = generate_mod_file(&table_names);
-        let mod_path = Path::new(output_path).join("mod.rs");
-        fs::write(&mod_path, mod_content)?;
+        for policy in &mut table.policies {
+            let query = format!(
+                "SELECT qual, with_check FROM pg_policies WHERE schemaname = {} AND tablename = {} AND policyname = {}",
+                quote_literal(&scratch),
+                quote_literal(name),
+                quote_literal(&policy.name)
+            );
+            if let Some(row) = scratch_deparse(&mut tx, &generate_create_policy(name, policy), &query).await? {
+                policy.using = row.get("qual");
+                policy.with_check = row.get("with_check");
+            }
+        }
 
-        println!("Generated {} model files in {}", toml_schema.table.len(), output_path);
+        for partition in &mut table.partitions {
+            let create = format!(
+                "CREATE TABLE \"{}\" PARTITION OF \"{}\" {}",
+                partition.name, name, partition.bound
+            );
+            let query = format!(
+                "SELECT pg_get_expr(relpartbound, oid) FROM pg_class WHERE oid = '\"{}\".\"{}\"'::regclass",
+                scratch, partition.name
+            );
+            if let Some(row) = scratch_deparse(&mut tx, &create, &query).await? {
+                partition.bound = row.get(0);
+            }
+        }
+    }
 
-        Ok(())
+    // The trigger's function may not exist yet, and doesn't change the
+    // condition, so every scratch trigger calls a stub
+    let stub = "trigger_stub";
+    let create_stub = format!(
+        "CREATE FUNCTION \"{}\".\"{}\"() RETURNS trigger LANGUAGE plpgsql AS 'BEGIN RETURN NULL; END'",
+        scratch, stub
+    );
+    if scratch_execute(&mut tx, &create_stub).await? {
+        for trigger in &mut deparsed.triggers {
+            if trigger.when.is_none() || !scratch_tables.contains(&trigger.table) {
+                continue;
+            }
+            let scratch_trigger = Trigger {
+                function: stub.to_string(),
+                ..trigger.clone()
+            };
+            let query = format!(
+                "SELECT pg_get_triggerdef(oid) FROM pg_trigger WHERE tgrelid = '\"{}\".\"{}\"'::regclass AND tgname = {}",
+                scratch,
+                trigger.table,
+                quote_literal(&trigger.name)
+            );
+            if let Some(row) = scratch_deparse(&mut tx, &generate_create_trigger(&scratch_trigger), &query).await? {
+                let definition: String = row.get(0);
+                trigger.when = trigger_when(&definition);
+            }
+        }
     }
+
+    tx.rollback().await?;
+    Ok(deparsed)
 }
 
-// ============ Migration Logic (private helpers) ============
+/// Run `sql` in a savepoint, undoing it when Postgres rejects it
+async fn scratch_execute(conn: &mut PgConnection, sql: &str) -> Result<bool> {
+    sqlx::query("SAVEPOINT deparse").execute(&mut *conn).await?;
+    let accepted = sqlx::query(sql).execute(&mut *conn).await.is_ok();
+    let end = if accepted { "RELEASE SAVEPOINT deparse" } else { "ROLLBACK TO SAVEPOINT deparse" };
+    sqlx::query(end).execute(&mut *conn).await?;
+    Ok(accepted)
+}
+
+/// Create an object with `create` and read it back through `query`, giving
+/// None when Postgres rejects it
+async fn scratch_deparse(conn: &mut PgConnection, create: &str, query: &str) -> Result<Option<PgRow>> {
+    if !scratch_execute(conn, create).await? {
+        return Ok(None);
+    }
+    Ok(sqlx::query(query).fetch_optional(&mut *conn).await?)
+}
 
 fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
     let mut migrations = Vec::new();
 
-    // Phase 1: Drop foreign keys that no longer exist
+    // Views dropped below are created from scratch later on, so the rest of
+    // the diff treats them (and the privileges they held) as gone
+    let dropped_views = find_dropped_views(current, target);
+    let mut view_drops: Vec<String> = order_views_by_dependency(&dropped_views, current)
+        .iter()
+        .map(|name| generate_drop_view(name, current.views[name].materialized))
+        .collect();
+    view_drops.reverse();
+
+    // So are triggers calling a function that is dropped to change its
+    // return type, since they would block the DROP
+    let dropped_triggers: Vec<Trigger> =
+        find_triggers_on_recreated_functions(current, target).into_iter().cloned().collect();
+
+    let mut current = current.clone();
+    for name in &dropped_views {
+        current.views.remove(name);
+    }
+    for grant in &mut current.grants {
+        grant.tables.retain(|table| !dropped_views.contains(table));
+    }
+    current
+        .triggers
+        .retain(|t| !dropped_triggers.iter().any(|d| d.name == t.name && d.table == t.table));
+    let current = &current;
+
+    // Phase 1: Create missing extensions, which column types and defaults may rely on
+    for extension in &target.extensions {
+        if !current.extensions.contains(extension) {
+            migrations.push(generate_create_extension(extension));
+        }
+    }
+
+    // Phase 2: Create or alter domains and composite types used by columns
+    for (current_domain, domain) in find_new_or_changed_domains(current, target) {
+        migrations.extend(generate_domain_migrations(current_domain, domain));
+    }
+    for composite in &target.composite_types {
+        let current_composite = current.composite_types.iter().find(|c| c.name == composite.name);
+        migrations.extend(generate_composite_type_migrations(current_composite, composite));
+    }
+
+    // Phase 3: Drop foreign keys that no longer exist
     for (table_name, current_table) in &current.tables {
         if let Some(target_table) = target.tables.get(table_name) {
             let dropped_fks = find_dropped_foreign_keys(current_table, target_table);
@@ -277,8 +1101,28 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
-    // Phase 2: Create new tables (order by dependencies)
+    // Phase 4: Drop removed views, and views that must be recreated rather
+    // than replaced, dependents first; then triggers on recreated functions
+    migrations.extend(view_drops);
+    migrations.extend(dropped_triggers.iter().map(generate_drop_trigger));
+
+    // Phase 5: Create or replace functions that don't reference new tables or
+    // views, so column defaults can call them
     let new_tables = find_new_tables(current, target);
+    let changed_functions = find_new_or_changed_functions(current, target);
+    let new_relations: HashSet<String> = new_tables
+        .iter()
+        .chain(target.views.keys().filter(|name| !current.views.contains_key(*name)))
+        .map(|name| name.to_lowercase())
+        .collect();
+    let (early_functions, late_functions): (Vec<_>, Vec<_>) = changed_functions
+        .into_iter()
+        .partition(|(_, function)| sql_identifiers(&function.body).is_disjoint(&new_relations));
+    for (current_function, function) in early_functions {
+        migrations.extend(generate_function_migrations(current_function, function));
+    }
+
+    // Phase 6: Create new tables (order by dependencies)
     let ordered_tables = order_tables_by_dependency(&new_tables, target);
     for table_name in ordered_tables {
         if let Some(table) = target.tables.get(&table_name) {
@@ -286,7 +1130,17 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
-    // Phase 3: Alter existing tables (add/modify columns)
+    // Phase 7: Create, attach and detach partitions
+    for (table_name, target_table) in &target.tables {
+        migrations.extend(generate_partition_migrations(
+            table_name,
+            current,
+            current.tables.get(table_name),
+            target_table,
+        ));
+    }
+
+    // Phase 8: Alter existing tables (add/modify columns)
     for (table_name, target_table) in &target.tables {
         if let Some(current_table) = current.tables.get(table_name) {
             let new_columns = find_new_columns(current_table, target_table);
@@ -301,7 +1155,13 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
-    // Phase 4: Create new indexes
+    // Phase 9: Set table and column comments
+    for (table_name, target_table) in &target.tables {
+        let current_table = current.tables.get(table_name);
+        migrations.extend(generate_comment_migrations(table_name, current_table, target_table));
+    }
+
+    // Phase 10: Create new indexes
     for (table_name, target_table) in &target.tables {
         let current_table = current.tables.get(table_name);
         let new_indexes = find_new_indexes(current_table, target_table);
@@ -312,7 +1172,7 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
-    // Phase 5: Create new foreign keys
+    // Phase 11: Create new foreign keys
     for (table_name, target_table) in &target.tables {
         let current_table = current.tables.get(table_name);
         let new_fks = find_new_foreign_keys(current_table, target_table);
@@ -321,7 +1181,7 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
-    // Phase 6: Drop removed indexes
+    // Phase 12: Drop removed indexes
     for (table_name, current_table) in &current.tables {
         if let Some(target_table) = target.tables.get(table_name) {
             let dropped_indexes = find_dropped_indexes(current_table, target_table);
@@ -333,6 +1193,46 @@ fn generate_migrations(current: &Schema, target: &Schema) -> Vec<String> {
         }
     }
 
+    // Phase 13: Create or replace views (order by dependency)
+    let changed_views = find_new_or_changed_views(current, target);
+    let ordered_views = order_views_by_dependency(&changed_views, target);
+    for view_name in ordered_views {
+        if let Some(view) = target.views.get(&view_name) {
+            migrations.push(generate_create_view(&view_name, view));
+        }
+    }
+
+    // Phase 14: Create or replace the remaining functions
+    for (current_function, function) in late_functions {
+        migrations.extend(generate_function_migrations(current_function, function));
+    }
+
+    // Phase 15: Drop removed triggers, then create or recreate the rest
+    for trigger in find_dropped_triggers(current, target) {
+        migrations.push(generate_drop_trigger(trigger));
+    }
+    for (current_trigger, trigger) in find_new_or_changed_triggers(current, target) {
+        if current_trigger.is_some() {
+            migrations.push(generate_drop_trigger(trigger));
+        }
+        migrations.push(generate_create_trigger(trigger));
+    }
+
+    // Phase 16: Drop removed functions, once no trigger calls them
+    for function in find_dropped_functions(current, target) {
+        migrations.push(generate_drop_function(function));
+    }
+
+    // Phase 17: Converge row-level security policies, then the RLS flags
+    for (table_name, target_table) in &target.tables {
+        let current_table = current.tables.get(table_name);
+        migrations.extend(generate_policy_migrations(table_name, current_table, target_table));
+        migrations.extend(generate_rls_migrations(table_name, current_table, target_table));
+    }
+
+    // Phase 18: Grant and revoke privileges for the roles the schema manages
+    migrations.extend(generate_grant_migrations(current, target));
+
     migrations
 }
 
@@ -374,7 +1274,22 @@ fn find_changed_columns(current: &Table, target: &Table) -> Vec<(Column, Column)
 }
 
 fn columns_differ(a: &Column, b: &Column) -> bool {
-    a.data_type != b.data_type || a.is_nullable != b.is_nullable || a.default != b.default
+    canonical_type(&a.data_type) != canonical_type(&b.data_type)
+        || a.is_nullable != b.is_nullable
+        || defaults_differ(a, b)
+}
+
+fn defaults_differ(a: &Column, b: &Column) -> bool {
+    a.default.as_deref().map(normalize_sql) != b.default.as_deref().map(normalize_sql)
+}
+
+/// The default Postgres gives a serial column, drawing from `{table}_{column}_seq`
+pub(crate) fn serial_default(table: &str, column: &str) -> String {
+    let sequence = format!("{}_{}_seq", table, column);
+    let plain = !sequence.starts_with(|c: char| c.is_ascii_digit())
+        && sequence.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    let sequence = if plain { sequence } else { format!("\"{}\"", sequence.replace('"', "\"\"")) };
+    format!("nextval({}::regclass)", quote_literal(&sequence))
 }
 
 fn find_new_indexes(current: Option<&Table>, target: &Table) -> Vec<Index> {
@@ -434,6 +1349,599 @@ fn find_dropped_foreign_keys(current: &Table, target: &Table) -> Vec<ForeignKey>
         .collect()
 }
 
+fn find_new_or_changed_views(current: &Schema, target: &Schema) -> Vec<String> {
+    target
+        .views
+        .iter()
+        .filter(|(name, view)| match current.views.get(*name) {
+            Some(current_view) => views_differ(current_view, view),
+            None => true,
+        })
+        .map(|(name, _)| name.clone())
+        .collect()
+}
+
+/// Removed views, materialized views being redefined, views changing kind,
+/// and every view built on top of one of those
+fn find_dropped_views(current: &Schema, target: &Schema) -> Vec<String> {
+    let mut dropped: Vec<String> = current
+        .views
+        .iter()
+        .filter(|(name, view)| match target.views.get(*name) {
+            Some(target_view) => {
+                (view.materialized || target_view.materialized) && views_differ(view, target_view)
+            }
+            None => true,
+        })
+        .map(|(name, _)| name.clone())
+        .collect();
+
+    loop {
+        let dependents: Vec<String> = current
+            .views
+            .iter()
+            .filter(|(name, view)| {
+                let referenced = sql_identifiers(&view.definition);
+                !dropped.contains(name) && dropped.iter().any(|d| referenced.contains(&d.to_lowercase()))
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if dependents.is_empty() {
+            break;
+        }
+        dropped.extend(dependents);
+    }
+
+    dropped.sort();
+    dropped
+}
+
+fn views_differ(a: &View, b: &View) -> bool {
+    a.materialized != b.materialized || normalize_sql(&a.definition) != normalize_sql(&b.definition)
+}
+
+/// Collapse whitespace, case and trailing semicolons outside of quoted
+/// literals and identifiers, so formatting-only edits don't count as changes
+fn normalize_sql(sql: &str) -> String {
+    let mut normalized = String::with_capacity(sql.len());
+    let mut quote = None;
+    let mut space = false;
+
+    for c in sql.trim().trim_end_matches(';').trim_end().chars() {
+        match quote {
+            Some(q) => {
+                normalized.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c.is_whitespace() => space = true,
+            None => {
+                if space {
+                    normalized.push(' ');
+                    space = false;
+                }
+                if c == '\'' || c == '"' {
+                    quote = Some(c);
+                }
+                normalized.extend(c.to_lowercase());
+            }
+        }
+    }
+
+    normalized
+}
+
+/// The spelling `format_type` gives a type, so aliases such as `int4`,
+/// `varchar(40)` or `timestamptz` compare equal to introspected types
+fn canonical_type(data_type: &str) -> String {
+    let mut name = normalize_sql(data_type);
+    let mut array = String::new();
+    while let Some(element) = name.strip_suffix("[]") {
+        name = element.trim_end().to_string();
+        array.push_str("[]");
+    }
+
+    // `timestamp(3) with time zone` keeps its precision mid-name
+    let mut modifiers = String::new();
+    if let (Some(open), Some(close)) = (name.find('('), name.rfind(')'))
+        && open < close
+    {
+        modifiers = name[open..=close].split_whitespace().collect();
+        name = format!("{} {}", name[..open].trim(), name[close + 1..].trim()).trim().to_string();
+    }
+
+    let (base, suffix) = match name.as_str() {
+        "int" | "int4" | "integer" | "serial" | "serial4" => ("integer", ""),
+        "int8" | "bigint" | "bigserial" | "serial8" => ("bigint", ""),
+        "int2" | "smallint" | "smallserial" | "serial2" => ("smallint", ""),
+        "float" | "float8" | "double precision" => ("double precision", ""),
+        "float4" | "real" => ("real", ""),
+        "decimal" | "numeric" => ("numeric", ""),
+        "bool" | "boolean" => ("boolean", ""),
+        "varchar" | "character varying" => ("character varying", ""),
+        "char" | "character" | "bpchar" => {
+            if modifiers.is_empty() {
+                modifiers = "(1)".to_string();
+            }
+            ("character", "")
+        }
+        "varbit" | "bit varying" => ("bit varying", ""),
+        "timestamptz" | "timestamp with time zone" => ("timestamp", " with time zone"),
+        "timestamp" | "timestamp without time zone" => ("timestamp", " without time zone"),
+        "timetz" | "time with time zone" => ("time", " with time zone"),
+        "time" | "time without time zone" => ("time", " without time zone"),
+        other => (other, ""),
+    };
+
+    format!("{}{}{}{}", base, modifiers, suffix, array)
+}
+
+/// Identifiers referenced in a SQL snippet, lowercased and unquoted
+fn sql_identifiers(sql: &str) -> HashSet<String> {
+    sql.split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn order_views_by_dependency(views: &[String], schema: &Schema) -> Vec<String> {
+    let mut ordered = Vec::new();
+    let mut remaining: HashSet<_> = views.iter().cloned().collect();
+
+    while !remaining.is_empty() {
+        let mut added_this_round = Vec::new();
+
+        for view_name in &remaining {
+            if let Some(view) = schema.views.get(view_name) {
+                let referenced = sql_identifiers(&view.definition);
+                let deps_satisfied = remaining.iter().all(|other| {
+                    other == view_name || !referenced.contains(&other.to_lowercase())
+                });
+
+                if deps_satisfied {
+                    added_this_round.push(view_name.clone());
+                }
+            }
+        }
+
+        if added_this_round.is_empty() && !remaining.is_empty() {
+            ordered.extend(remaining.drain());
+            break;
+        }
+
+        added_this_round.sort();
+        for name in added_this_round {
+            remaining.remove(&name);
+            ordered.push(name);
+        }
+    }
+
+    ordered
+}
+
+fn policies_differ(a: &Policy, b: &Policy) -> bool {
+    let roles = |p: &Policy| {
+        let mut roles: Vec<String> = p.roles.iter().map(|r| r.to_lowercase()).collect();
+        roles.sort();
+        roles
+    };
+    let expr = |e: &Option<String>| e.as_deref().map(|e| normalize_sql(strip_outer_parens(e)));
+
+    !a.command.eq_ignore_ascii_case(&b.command)
+        || a.restrictive != b.restrictive
+        || roles(a) != roles(b)
+        || expr(&a.using) != expr(&b.using)
+        || expr(&a.with_check) != expr(&b.with_check)
+}
+
+/// A single privilege held by a role on one table or sequence
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct GrantedPrivilege {
+    role: String,
+    on_sequence: bool,
+    object: String,
+    privilege: String,
+}
+
+const TABLE_PRIVILEGES: &[&str] = &[
+    "SELECT", "INSERT", "UPDATE", "DELETE", "TRUNCATE", "REFERENCES", "TRIGGER",
+];
+const SEQUENCE_PRIVILEGES: &[&str] = &["USAGE", "SELECT", "UPDATE"];
+
+/// Flatten grants into individual privileges, expanding `*` and `ALL`
+fn expand_grants(schema: &Schema) -> BTreeSet<GrantedPrivilege> {
+    let mut all_tables: Vec<String> = schema
+        .tables
+        .keys()
+        .chain(schema.views.keys())
+        .cloned()
+        .collect();
+    all_tables.sort();
+    let all_sequences = sequence_names(schema);
+
+    let mut expanded = BTreeSet::new();
+
+    for grant in &schema.grants {
+        let objects = |names: &[String], all: &[String]| -> Vec<String> {
+            if names.iter().any(|n| n == "*") {
+                all.to_vec()
+            } else {
+                names.to_vec()
+            }
+        };
+
+        let targets = objects(&grant.tables, &all_tables)
+            .into_iter()
+            .map(|object| (false, object))
+            .chain(
+                objects(&grant.sequences, &all_sequences)
+                    .into_iter()
+                    .map(|object| (true, object)),
+            );
+
+        for (on_sequence, object) in targets {
+            let allowed = if on_sequence { SEQUENCE_PRIVILEGES } else { TABLE_PRIVILEGES };
+            for privilege in &grant.privileges {
+                let privilege = privilege.to_uppercase();
+                let privileges: Vec<&str> = if privilege == "ALL" || privilege == "ALL PRIVILEGES" {
+                    allowed.to_vec()
+                } else {
+                    vec![privilege.as_str()]
+                };
+
+                for privilege in privileges {
+                    expanded.insert(GrantedPrivilege {
+                        role: grant.role.clone(),
+                        on_sequence,
+                        object: object.clone(),
+                        privilege: privilege.to_string(),
+                    });
+                }
+            }
+        }
+    }
+
+    expanded
+}
+
+/// Sequences named in `nextval('...')` column defaults
+fn sequence_names(schema: &Schema) -> Vec<String> {
+    let mut names: Vec<String> = schema
+        .tables
+        .values()
+        .flat_map(|t| &t.columns)
+        .filter_map(|c| c.default.as_deref())
+        .filter_map(|default| {
+            let start = default.find("nextval('")? + "nextval('".len();
+            let end = start + default[start..].find('\'')?;
+            Some(default[start..end].trim_matches('"').to_string())
+        })
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// Group individual privileges back into one Grant per role and privilege set
+fn group_privileges(privileges: &BTreeSet<GrantedPrivilege>) -> Vec<Grant> {
+    let mut per_object: BTreeMap<(&str, bool, &str), Vec<String>> = BTreeMap::new();
+    for p in privileges {
+        per_object
+            .entry((&p.role, p.on_sequence, &p.object))
+            .or_default()
+            .push(p.privilege.clone());
+    }
+
+    let mut grouped: BTreeMap<(String, Vec<String>), Grant> = BTreeMap::new();
+    for ((role, on_sequence, object), privileges) in per_object {
+        let grant = grouped
+            .entry((role.to_string(), privileges.clone()))
+            .or_insert_with(|| Grant {
+                role: role.to_string(),
+                privileges,
+                tables: Vec::new(),
+                sequences: Vec::new(),
+            });
+        if on_sequence {
+            grant.sequences.push(object.to_string());
+        } else {
+            grant.tables.push(object.to_string());
+        }
+    }
+
+    grouped.into_values().collect()
+}
+
+/// Pairs each new or changed domain with its current version, if any
+fn find_new_or_changed_domains<'a>(
+    current: &'a Schema,
+    target: &'a Schema,
+) -> Vec<(Option<&'a Domain>, &'a Domain)> {
+    target
+        .domains
+        .iter()
+        .filter_map(|domain| {
+            let current_domain = current.domains.iter().find(|d| d.name == domain.name);
+            match current_domain {
+                Some(existing) if !domains_differ(existing, domain) => None,
+                _ => Some((current_domain, domain)),
+            }
+        })
+        .collect()
+}
+
+fn domains_differ(a: &Domain, b: &Domain) -> bool {
+    a.not_null != b.not_null
+        || a.default.as_deref().map(normalize_sql) != b.default.as_deref().map(normalize_sql)
+        || a.check.as_deref().map(|c| normalize_sql(strip_outer_parens(c)))
+            != b.check.as_deref().map(|c| normalize_sql(strip_outer_parens(c)))
+}
+
+/// Pairs each new or changed function with its current version, if any
+fn find_new_or_changed_functions<'a>(
+    current: &'a Schema,
+    target: &'a Schema,
+) -> Vec<(Option<&'a Function>, &'a Function)> {
+    target
+        .functions
+        .iter()
+        .filter_map(|function| {
+            let current_function = current
+                .functions
+                .iter()
+                .find(|f| same_function_signature(f, function));
+            match current_function {
+                Some(existing) if !functions_differ(existing, function) => None,
+                _ => Some((current_function, function)),
+            }
+        })
+        .collect()
+}
+
+/// Functions whose signature is no longer in the target, including the old
+/// overload when a function's arguments change
+fn find_dropped_functions<'a>(current: &'a Schema, target: &Schema) -> Vec<&'a Function> {
+    current
+        .functions
+        .iter()
+        .filter(|function| !target.functions.iter().any(|f| same_function_signature(f, function)))
+        .collect()
+}
+
+fn same_function_signature(a: &Function, b: &Function) -> bool {
+    a.name == b.name
+        && normalize_sql(&identity_arguments(&a.arguments))
+            == normalize_sql(&identity_arguments(&b.arguments))
+}
+
+fn functions_differ(a: &Function, b: &Function) -> bool {
+    normalize_sql(&a.returns) != normalize_sql(&b.returns)
+        || normalize_sql(&a.language) != normalize_sql(&b.language)
+        || normalize_sql(&a.arguments) != normalize_sql(&b.arguments)
+        || !a.volatility.eq_ignore_ascii_case(&b.volatility)
+        || a.strict != b.strict
+        || a.security_definer != b.security_definer
+        || a.search_path.as_deref().map(normalize_sql) != b.search_path.as_deref().map(normalize_sql)
+        || normalize_sql(&a.body) != normalize_sql(&b.body)
+}
+
+/// Strip argument defaults, leaving the list Postgres uses to identify a function
+fn identity_arguments(arguments: &str) -> String {
+    split_top_level(arguments)
+        .into_iter()
+        .map(|arg| {
+            let lower = arg.to_lowercase();
+            let end = [lower.find(" default "), lower.find('=')]
+                .into_iter()
+                .flatten()
+                .min()
+                .unwrap_or(arg.len());
+            arg[..end].trim()
+        })
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Split on commas outside of parentheses and quotes, so `numeric(10,2)`
+/// and `DEFAULT 'a,b'` stay whole
+fn split_top_level(list: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut quote = None;
+
+    for (i, c) in list.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                parts.push(&list[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&list[start..]);
+
+    parts
+}
+
+/// Pairs each new or changed trigger with its current version, if any
+fn find_new_or_changed_triggers<'a>(
+    current: &'a Schema,
+    target: &'a Schema,
+) -> Vec<(Option<&'a Trigger>, &'a Trigger)> {
+    target
+        .triggers
+        .iter()
+        .filter_map(|trigger| {
+            let current_trigger = current
+                .triggers
+                .iter()
+                .find(|t| t.name == trigger.name && t.table == trigger.table);
+            match current_trigger {
+                Some(existing) if !triggers_differ(existing, trigger) => None,
+                _ => Some((current_trigger, trigger)),
+            }
+        })
+        .collect()
+}
+
+/// Current triggers calling a function whose return type changes, which
+/// CREATE OR REPLACE can't do
+fn find_triggers_on_recreated_functions<'a>(current: &'a Schema, target: &'a Schema) -> Vec<&'a Trigger> {
+    let recreated: Vec<&Function> = find_new_or_changed_functions(current, target)
+        .into_iter()
+        .filter_map(|(current_function, function)| {
+            current_function.filter(|f| normalize_sql(&f.returns) != normalize_sql(&function.returns))
+        })
+        .collect();
+
+    // Trigger functions take no declared arguments
+    current
+        .triggers
+        .iter()
+        .filter(|t| recreated.iter().any(|f| f.name == t.function && identity_arguments(&f.arguments).is_empty()))
+        .collect()
+}
+
+fn find_dropped_triggers<'a>(current: &'a Schema, target: &Schema) -> Vec<&'a Trigger> {
+    current
+        .triggers
+        .iter()
+        .filter(|trigger| !target.triggers.iter().any(|t| t.name == trigger.name && t.table == trigger.table))
+        .collect()
+}
+
+fn triggers_differ(a: &Trigger, b: &Trigger) -> bool {
+    let events = |t: &Trigger| {
+        let mut events: Vec<String> = t.events.iter().map(|e| e.to_uppercase()).collect();
+        events.sort();
+        events
+    };
+
+    !a.timing.eq_ignore_ascii_case(&b.timing)
+        || !a.for_each.eq_ignore_ascii_case(&b.for_each)
+        || a.function != b.function
+        || a.arguments != b.arguments
+        || events(a) != events(b)
+        || a.when.as_deref().map(|w| normalize_sql(strip_outer_parens(w)))
+            != b.when.as_deref().map(|w| normalize_sql(strip_outer_parens(w)))
+}
+
+/// The WHEN condition of a `pg_get_triggerdef` definition, since pg_trigger
+/// keeps it as a node tree
+fn trigger_when(definition: &str) -> Option<String> {
+    definition.find(" WHEN (").and_then(|start| {
+        let rest = &definition[start + " WHEN ".len()..];
+        rest.rfind(" EXECUTE ")
+            .map(|end| strip_outer_parens(&rest[..end]).to_string())
+    })
+}
+
+/// Build a Trigger from pg_trigger, decoding the tgtype bit flags
+fn trigger_from_pg(
+    name: String,
+    table: String,
+    trigger_type: i32,
+    function: String,
+    definition: &str,
+) -> Trigger {
+    const ROW: i32 = 1 << 0;
+    const BEFORE: i32 = 1 << 1;
+    const INSERT: i32 = 1 << 2;
+    const DELETE: i32 = 1 << 3;
+    const UPDATE: i32 = 1 << 4;
+    const TRUNCATE: i32 = 1 << 5;
+    const INSTEAD: i32 = 1 << 6;
+
+    let timing = if trigger_type & INSTEAD != 0 {
+        "INSTEAD OF"
+    } else if trigger_type & BEFORE != 0 {
+        "BEFORE"
+    } else {
+        "AFTER"
+    };
+
+    let events = [(INSERT, "INSERT"), (UPDATE, "UPDATE"), (DELETE, "DELETE"), (TRUNCATE, "TRUNCATE")]
+        .into_iter()
+        .filter(|(flag, _)| trigger_type & flag != 0)
+        .map(|(_, event)| event.to_string())
+        .collect();
+
+    let when = trigger_when(definition);
+
+    // Arguments are deparsed as string literals: `EXECUTE FUNCTION f('a', 'b')`
+    let mut arguments = Vec::new();
+    if let Some(call) = definition
+        .rfind(" EXECUTE ")
+        .and_then(|start| definition[start..].find('(').map(|open| &definition[start + open..]))
+    {
+        let mut current: Option<String> = None;
+        let mut chars = call.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (&mut current, c) {
+                (Some(text), '\'') if chars.peek() == Some(&'\'') => {
+                    text.push('\'');
+                    chars.next();
+                }
+                (Some(_), '\'') => arguments.extend(current.take()),
+                (Some(text), c) => text.push(c),
+                (None, '\'') => current = Some(String::new()),
+                (None, _) => {}
+            }
+        }
+    }
+
+    Trigger {
+        name,
+        table,
+        timing: timing.to_string(),
+        events,
+        for_each: if trigger_type & ROW != 0 { "ROW" } else { "STATEMENT" }.to_string(),
+        function,
+        arguments,
+        when,
+    }
+}
+
+/// Join a domain's CHECK constraint definitions into one expression.
+/// Postgres parenthesizes each deparsed expression, so they can be joined
+/// without changing precedence.
+fn joined_checks(definitions: &[String]) -> Option<String> {
+    let checks: Vec<&str> = definitions
+        .iter()
+        .map(|def| strip_outer_parens(def.trim_start_matches("CHECK ")))
+        .collect();
+    (!checks.is_empty()).then(|| checks.join(" AND "))
+}
+
+/// Remove one pair of parentheses wrapping the whole expression, if present
+fn strip_outer_parens(sql: &str) -> &str {
+    let trimmed = sql.trim();
+    let Some(inner) = trimmed.strip_prefix('(').and_then(|s| s.strip_suffix(')')) else {
+        return trimmed;
+    };
+
+    // `(a) AND (b)` starts and ends with parens that don't belong together
+    let mut depth = 0;
+    for c in inner.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return trimmed,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    inner.trim()
+}
+
 fn order_tables_by_dependency(tables: &[String], schema: &Schema) -> Vec<String> {
     let mut ordered = Vec::new();
     let mut remaining: HashSet<_> = tables.iter().cloned().collect();
@@ -471,8 +1979,138 @@ fn order_tables_by_dependency(tables: &[String], schema: &Schema) -> Vec<String>
 
 // ============ SQL Generation ============
 
+/// Parse `pg_get_partkeydef` output such as `RANGE (created_at)`
+fn parse_partition_key(definition: &str) -> Option<PartitionBy> {
+    let (strategy, columns) = definition.split_once(' ')?;
+    let columns = strip_outer_parens(columns);
+
+    Some(PartitionBy {
+        strategy: strategy.to_lowercase(),
+        columns: columns.split(',').map(|c| c.trim().to_string()).collect(),
+    })
+}
+
+fn generate_create_extension(name: &str) -> String {
+    format!("CREATE EXTENSION IF NOT EXISTS \"{}\"", name)
+}
+
+/// A domain's base type can't be altered in place, so only its default,
+/// NOT NULL and CHECK are converged once it exists
+fn generate_domain_migrations(current: Option<&Domain>, target: &Domain) -> Vec<String> {
+    let constraint_name = format!("{}_check", target.name);
+
+    let Some(current) = current else {
+        return vec![generate_create_domain(&format!("\"{}\"", target.name), target)];
+    };
+
+    let mut migrations = Vec::new();
+
+    if current.default.as_deref().map(normalize_sql) != target.default.as_deref().map(normalize_sql) {
+        migrations.push(match &target.default {
+            Some(default) => format!("ALTER DOMAIN \"{}\" SET DEFAULT {}", target.name, default),
+            None => format!("ALTER DOMAIN \"{}\" DROP DEFAULT", target.name),
+        });
+    }
+
+    if current.not_null != target.not_null {
+        let action = if target.not_null { "SET" } else { "DROP" };
+        migrations.push(format!("ALTER DOMAIN \"{}\" {} NOT NULL", target.name, action));
+    }
+
+    if current.check.as_deref().map(|c| normalize_sql(strip_outer_parens(c)))
+        != target.check.as_deref().map(|c| normalize_sql(strip_outer_parens(c)))
+    {
+        // The current check may be several constraints joined with AND
+        for name in &current.check_constraints {
+            migrations.push(format!(
+                "ALTER DOMAIN \"{}\" DROP CONSTRAINT IF EXISTS \"{}\"",
+                target.name, name
+            ));
+        }
+        if let Some(check) = &target.check {
+            migrations.push(format!(
+                "ALTER DOMAIN \"{}\" ADD CONSTRAINT \"{}\" CHECK ({})",
+                target.name,
+                constraint_name,
+                strip_outer_parens(check)
+            ));
+        }
+    }
+
+    migrations
+}
+
+fn generate_create_domain(qualified_name: &str, domain: &Domain) -> String {
+    let mut sql = format!("CREATE DOMAIN {} AS {}", qualified_name, domain.base_type);
+    if let Some(default) = &domain.default {
+        sql.push_str(&format!(" DEFAULT {}", default));
+    }
+    if domain.not_null {
+        sql.push_str(" NOT NULL");
+    }
+    if let Some(check) = &domain.check {
+        sql.push_str(&format!(
+            " CONSTRAINT \"{}_check\" CHECK ({})",
+            domain.name,
+            strip_outer_parens(check)
+        ));
+    }
+    sql
+}
+
+fn generate_composite_type_migrations(
+    current: Option<&CompositeType>,
+    target: &CompositeType,
+) -> Vec<String> {
+    let Some(current) = current else {
+        let attributes: Vec<String> = target
+            .attribute
+            .iter()
+            .map(|a| format!("\"{}\" {}", a.name, map_data_type(&a.data_type)))
+            .collect();
+        return vec![format!(
+            "CREATE TYPE \"{}\" AS ({})",
+            target.name,
+            attributes.join(", ")
+        )];
+    };
+
+    let mut migrations = Vec::new();
+
+    for attr in &current.attribute {
+        if !target.attribute.iter().any(|a| a.name == attr.name) {
+            migrations.push(format!(
+                "ALTER TYPE \"{}\" DROP ATTRIBUTE \"{}\"",
+                target.name, attr.name
+            ));
+        }
+    }
+
+    for attr in &target.attribute {
+        match current.attribute.iter().find(|a| a.name == attr.name) {
+            None => migrations.push(format!(
+                "ALTER TYPE \"{}\" ADD ATTRIBUTE \"{}\" {}",
+                target.name,
+                attr.name,
+                map_data_type(&attr.data_type)
+            )),
+            Some(existing) if canonical_type(&existing.data_type) != canonical_type(&attr.data_type) => {
+                migrations.push(format!(
+                    "ALTER TYPE \"{}\" ALTER ATTRIBUTE \"{}\" TYPE {}",
+                    target.name,
+                    attr.name,
+                    map_data_type(&attr.data_type)
+                ))
+            }
+            Some(_) => {}
+        }
+    }
+
+    migrations
+}
+
 fn generate_create_table(name: &str, table: &Table) -> String {
-    let columns: Vec<String> = table.columns.iter().map(|c| format_column_def(c)).collect();
+    let columns: Vec<String> = table.columns.iter().map(|col| format_column_def(name, col)).collect();
 
     let pk = table.indexes.iter().find(|i| i.name.ends_with("_pkey"));
 
@@ -482,28 +2120,105 @@ fn generate_create_table(name: &str, table: &Table) -> String {
         parts.push(format!("PRIMARY KEY ({})", pk_idx.columns.join(", ")));
     }
 
-    format!("CREATE TABLE \"{}\" (\n  {}\n)", name, parts.join(",\n  "))
+    let partition_clause = match &table.partition_by {
+        Some(partition_by) => format!(
+            " PARTITION BY {} ({})",
+            partition_by.strategy.to_uppercase(),
+            partition_by.columns.join(", ")
+        ),
+        None => String::new(),
+    };
+
+    format!(
+        "CREATE TABLE \"{}\" (\n  {}\n){}",
+        name,
+        parts.join(",\n  "),
+        partition_clause
+    )
+}
+
+/// New partitions are created, existing standalone tables are attached, and
+/// partitions that were removed or rebounded are detached (keeping their data)
+fn generate_partition_migrations(
+    table: &str,
+    current_schema: &Schema,
+    current: Option<&Table>,
+    target: &Table,
+) -> Vec<String> {
+    let mut migrations = Vec::new();
+    let current_partitions = current.map(|t| t.partitions.as_slice()).unwrap_or_default();
+
+    for partition in current_partitions {
+        let unchanged = target.partitions.iter().any(|p| {
+            p.name == partition.name && normalize_sql(&p.bound) == normalize_sql(&partition.bound)
+        });
+        if !unchanged {
+            migrations.push(format!(
+                "ALTER TABLE \"{}\" DETACH PARTITION \"{}\"",
+                table, partition.name
+            ));
+        }
+    }
+
+    for partition in &target.partitions {
+        let existing = current_partitions.iter().find(|p| p.name == partition.name);
+        if existing.is_some_and(|p| normalize_sql(&p.bound) == normalize_sql(&partition.bound)) {
+            continue;
+        }
+
+        if existing.is_some() || current_schema.tables.contains_key(&partition.name) {
+            migrations.push(format!(
+                "ALTER TABLE \"{}\" ATTACH PARTITION \"{}\" {}",
+                table, partition.name, partition.bound
+            ));
+        } else {
+            migrations.push(format!(
+                "CREATE TABLE \"{}\" PARTITION OF \"{}\" {}",
+                partition.name, table, partition.bound
+            ));
+        }
+    }
+
+    migrations
 }
 
-fn format_column_def(col: &Column) -> String {
-    let mut def = format!("\"{}\" {}", col.name, map_data_type(&col.data_type));
+fn format_column_def(table: &str, col: &Column) -> String {
+    let (data_type, default) = column_declaration(table, col);
+    let mut def = format!("\"{}\" {}", col.name, data_type);
 
     if !col.is_nullable {
         def.push_str(" NOT NULL");
     }
 
-    if let Some(default) = &col.default {
-        if !default.contains("nextval") {
-            def.push_str(&format!(" DEFAULT {}", default));
-        }
+    if let Some(default) = default
+        && !default.contains("nextval")
+    {
+        def.push_str(&format!(" DEFAULT {}", default));
     }
 
     def
 }
 
+/// The type and default to declare a column with. A serial column's own
+/// sequence default is declared as serial again, which creates the sequence.
+fn column_declaration<'c>(table: &str, col: &'c Column) -> (&'c str, Option<&'c str>) {
+    if col.default.as_deref() == Some(serial_default(table, &col.name).as_str()) {
+        let serial = match canonical_type(&col.data_type).as_str() {
+            "integer" => Some("serial"),
+            "bigint" => Some("bigserial"),
+            "smallint" => Some("smallserial"),
+            _ => None,
+        };
+        if let Some(serial) = serial {
+            return (serial, None);
+        }
+    }
+
+    (map_data_type(&col.data_type), col.default.as_deref())
+}
+
 fn map_data_type(pg_type: &str) -> &str {
     match pg_type {
-        "character varying" => "VARCHAR(255)",
         "timestamp without time zone" => "TIMESTAMP",
         "timestamp with time zone" => "TIMESTAMPTZ",
         _ => pg_type,
@@ -511,57 +2226,233 @@ fn map_data_type(pg_type: &str) -> &str {
 }
 
 fn generate_add_column(table: &str, col: &Column) -> String {
+    let (data_type, default) = column_declaration(table, col);
     let mut sql = format!(
         "ALTER TABLE \"{}\" ADD COLUMN \"{}\" {}",
-        table,
-        col.name,
-        map_data_type(&col.data_type)
+        table, col.name, data_type
     );
 
     if !col.is_nullable {
         sql.push_str(" NOT NULL");
     }
 
-    if let Some(default) = &col.default {
+    if let Some(default) = default {
         sql.push_str(&format!(" DEFAULT {}", default));
     }
 
     sql
 }
 
-fn generate_alter_column(table: &str, _old: &Column, new: &Column) -> Vec<String> {
+/// Only the parts that changed are altered, since a partition key column
+/// rejects ALTER TYPE even when the type stays the same
+fn generate_alter_column(table: &str, old: &Column, new: &Column) -> Vec<String> {
+    let mut migrations = Vec::new();
+
+    if canonical_type(&old.data_type) != canonical_type(&new.data_type) {
+        migrations.push(format!(
+            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" TYPE {} USING \"{}\"::{}",
+            table,
+            new.name,
+            map_data_type(&new.data_type),
+            new.name,
+            map_data_type(&new.data_type)
+        ));
+    }
+
+    if old.is_nullable != new.is_nullable {
+        let action = if new.is_nullable { "DROP" } else { "SET" };
+        migrations.push(format!(
+            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" {} NOT NULL",
+            table, new.name, action
+        ));
+    }
+
+    if defaults_differ(old, new) {
+        migrations.push(match &new.default {
+            Some(default) => format!(
+                "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" SET DEFAULT {}",
+                table, new.name, default
+            ),
+            None => format!(
+                "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" DROP DEFAULT",
+                table, new.name
+            ),
+        });
+    }
+
+    migrations
+}
+
+/// COMMENT ON for every table or column comment that was added, changed or removed
+fn generate_comment_migrations(table: &str, current: Option<&Table>, target: &Table) -> Vec<String> {
+    let mut migrations = Vec::new();
+
+    let current_comment = current.and_then(|t| t.comment.as_deref());
+    if current_comment != target.comment.as_deref() {
+        migrations.push(format!(
+            "COMMENT ON TABLE \"{}\" IS {}",
+            table,
+            comment_literal(target.comment.as_deref())
+        ));
+    }
+
+    for col in &target.columns {
+        let current_comment = current
+            .and_then(|t| t.columns.iter().find(|c| c.name == col.name))
+            .and_then(|c| c.comment.as_deref());
+        if current_comment != col.comment.as_deref() {
+            migrations.push(format!(
+                "COMMENT ON COLUMN \"{}\".\"{}\" IS {}",
+                table,
+                col.name,
+                comment_literal(col.comment.as_deref())
+            ));
+        }
+    }
+
+    migrations
+}
+
+fn comment_literal(comment: Option<&str>) -> String {
+    match comment {
+        Some(text) => quote_literal(text),
+        None => "NULL".to_string(),
+    }
+}
+
+fn quote_literal(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''"))
+}
+
+/// Drop removed or changed policies and create new or changed ones
+/// (ALTER POLICY can't change the command, so changes are recreated)
+fn generate_policy_migrations(table: &str, current: Option<&Table>, target: &Table) -> Vec<String> {
     let mut migrations = Vec::new();
+    let current_policies = current.map(|t| t.policies.as_slice()).unwrap_or_default();
+
+    for policy in current_policies {
+        let keep = target
+            .policies
+            .iter()
+            .any(|p| p.name == policy.name && !policies_differ(policy, p));
+        if !keep {
+            migrations.push(format!(
+                "DROP POLICY IF EXISTS \"{}\" ON \"{}\"",
+                policy.name, table
+            ));
+        }
+    }
+
+    for policy in &target.policies {
+        let unchanged = current_policies
+            .iter()
+            .any(|p| p.name == policy.name && !policies_differ(p, policy));
+        if !unchanged {
+            migrations.push(generate_create_policy(table, policy));
+        }
+    }
+
+    migrations
+}
+
+fn generate_create_policy(table: &str, policy: &Policy) -> String {
+    let roles: Vec<String> = policy
+        .roles
+        .iter()
+        .map(|role| {
+            // PUBLIC and CURRENT_USER style keywords must stay unquoted
+            if matches!(
+                role.to_lowercase().as_str(),
+                "public" | "current_user" | "current_role" | "session_user"
+            ) {
+                role.to_uppercase()
+            } else {
+                format!("\"{}\"", role)
+            }
+        })
+        .collect();
 
-    migrations.push(format!(
-        "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" TYPE {} USING \"{}\"::{}",
+    let mut sql = format!(
+        "CREATE POLICY \"{}\" ON \"{}\" AS {} FOR {} TO {}",
+        policy.name,
         table,
-        new.name,
-        map_data_type(&new.data_type),
-        new.name,
-        map_data_type(&new.data_type)
-    ));
+        if policy.restrictive { "RESTRICTIVE" } else { "PERMISSIVE" },
+        policy.command.to_uppercase(),
+        roles.join(", ")
+    );
+
+    if let Some(using) = &policy.using {
+        sql.push_str(&format!(" USING ({})", strip_outer_parens(using)));
+    }
+    if let Some(with_check) = &policy.with_check {
+        sql.push_str(&format!(" WITH CHECK ({})", strip_outer_parens(with_check)));
+    }
 
-    if new.is_nullable {
+    sql
+}
+
+fn generate_rls_migrations(table: &str, current: Option<&Table>, target: &Table) -> Vec<String> {
+    let mut migrations = Vec::new();
+
+    if current.map(|t| t.rls_enabled).unwrap_or(false) != target.rls_enabled {
+        let action = if target.rls_enabled { "ENABLE" } else { "DISABLE" };
         migrations.push(format!(
-            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" DROP NOT NULL",
-            table, new.name
+            "ALTER TABLE \"{}\" {} ROW LEVEL SECURITY",
+            table, action
         ));
-    } else {
+    }
+
+    if current.map(|t| t.force_rls).unwrap_or(false) != target.force_rls {
+        let action = if target.force_rls { "FORCE" } else { "NO FORCE" };
         migrations.push(format!(
-            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" SET NOT NULL",
-            table, new.name
+            "ALTER TABLE \"{}\" {} ROW LEVEL SECURITY",
+            table, action
         ));
     }
 
-    match &new.default {
-        Some(default) => migrations.push(format!(
-            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" SET DEFAULT {}",
-            table, new.name, default
-        )),
-        None => migrations.push(format!(
-            "ALTER TABLE \"{}\" ALTER COLUMN \"{}\" DROP DEFAULT",
-            table, new.name
-        )),
+    migrations
+}
+
+/// GRANT what's missing and REVOKE what's extra. Roles that don't appear in
+/// any grant are left alone, so adopting grants doesn't strip existing access.
+fn generate_grant_migrations(current: &Schema, target: &Schema) -> Vec<String> {
+    let managed_roles: HashSet<&str> = target.grants.iter().map(|g| g.role.as_str()).collect();
+    let current_privileges: BTreeSet<GrantedPrivilege> = expand_grants(current)
+        .into_iter()
+        .filter(|p| managed_roles.contains(p.role.as_str()))
+        .collect();
+    let target_privileges = expand_grants(target);
+
+    let mut migrations = Vec::new();
+
+    for (action, privileges) in [
+        ("REVOKE", current_privileges.difference(&target_privileges)),
+        ("GRANT", target_privileges.difference(&current_privileges)),
+    ] {
+        let mut per_object: BTreeMap<(&str, bool, &str), Vec<&str>> = BTreeMap::new();
+        for p in privileges {
+            per_object
+                .entry((&p.role, p.on_sequence, &p.object))
+                .or_default()
+                .push(&p.privilege);
+        }
+
+        for ((role, on_sequence, object), privileges) in per_object {
+            let role = if role.eq_ignore_ascii_case("public") {
+                "PUBLIC".to_string()
+            } else {
+                format!("\"{}\"", role)
+            };
+            migrations.push(format!(
+                "{} {} ON {} \"{}\" {} {}",
+                action,
+                privileges.join(", "),
+                if on_sequence { "SEQUENCE" } else { "TABLE" },
+                object,
+                if action == "GRANT" { "TO" } else { "FROM" },
+                role
+            ));
+        }
     }
 
     migrations
@@ -601,231 +2492,121 @@ fn generate_drop_fk(table: &str, fk: &ForeignKey) -> String {
     )
 }
 
-// ============ Codegen Helpers ============
-
-/// Returns (belongs_to, has_many) maps
-fn build_relation_maps(schema: &TomlSchema) -> (
-    HashMap<String, Vec<(String, String, String)>>,
-    HashMap<String, Vec<String>>,
-) {
-    let mut belongs_to: HashMap<String, Vec<(String, String, String)>> = HashMap::new();
-    let mut has_many: HashMap<String, Vec<String>> = HashMap::new();
-
-    for table in &schema.table {
-        for fk in &table.foreign_key {
-            belongs_to
-                .entry(table.name.clone())
-                .or_default()
-                .push((
-                    fk.column.clone(),
-                    fk.referenced_table.clone(),
-                    fk.referenced_column.clone(),
-                ));
-
-            has_many
-                .entry(fk.referenced_table.clone())
-                .or_default()
-                .push(table.name.clone());
-        }
-    }
-
-    (belongs_to, has_many)
+/// Materialized views have no CREATE OR REPLACE, so changed ones were
+/// dropped earlier and are created afresh
+fn generate_create_view(name: &str, view: &View) -> String {
+    let kind = if view.materialized { "MATERIALIZED VIEW" } else { "OR REPLACE VIEW" };
+    format!(
+        "CREATE {} \"{}\" AS {}",
+        kind,
+        name,
+        view.definition.trim().trim_end_matches(';')
+    )
 }
 
-fn generate_table_file(
-    table: &TomlTable,
-    belongs_to: &HashMap<String, Vec<(String, String, String)>>,
-    has_many: &HashMap<String, Vec<String>>,
-    all_tables: &[&str],
-) -> String {
-    let struct_name = to_struct_name(&table.name);
-    let mut lines = Vec::new();
-
-    let mut needs_chrono = false;
-    let mut needs_decimal = false;
-    let mut needs_uuid = false;
-    let mut needs_json = false;
-
-    for col in &table.column {
-        let rust_type = pg_type_to_rust(&col.data_type, col.is_nullable);
-        if rust_type.contains("chrono::") {
-            needs_chrono = true;
-        }
-        if rust_type.contains("Decimal") {
-            needs_decimal = true;
-        }
-        if rust_type.contains("Uuid") {
-            needs_uuid = true;
-        }
-        if rust_type.contains("serde_json::") {
-            needs_json = true;
-        }
-    }
+fn generate_drop_view(name: &str, materialized: bool) -> String {
+    let kind = if materialized { "MATERIALIZED VIEW" } else { "VIEW" };
+    format!("DROP {} IF EXISTS \"{}\"", kind, name)
+}
 
-    // Collect relation imports
-    let mut relation_imports = HashSet::new();
+fn generate_function_migrations(current: Option<&Function>, target: &Function) -> Vec<String> {
+    let mut migrations = Vec::new();
 
-    if let Some(bt_relations) = belongs_to.get(&table.name) {
-        for (_, parent_table, _) in bt_relations {
-            if all_tables.contains(&parent_table.as_str()) && parent_table != &table.name {
-                relation_imports.insert(parent_table.clone());
-            }
-        }
+    // CREATE OR REPLACE can't change a function's return type
+    if let Some(current_function) = current
+        && normalize_sql(&current_function.returns) != normalize_sql(&target.returns)
+    {
+        migrations.push(generate_drop_function(current_function));
     }
 
-    if let Some(hm_relations) = has_many.get(&table.name) {
-        for child_table in hm_relations {
-            if all_tables.contains(&child_table.as_str()) && child_table != &table.name {
-                relation_imports.insert(child_table.clone());
-            }
-        }
-    }
+    migrations.push(generate_create_function(&format!("\"{}\"", target.name), target));
 
-    // Build imports section
-    lines.push("use sqlx::FromRow;".to_string());
+    migrations
+}
 
-    if needs_chrono {
-        lines.push("use chrono;".to_string());
-    }
-    if needs_decimal {
-        lines.push("use rust_decimal::Decimal;".to_string());
-    }
-    if needs_uuid {
-        lines.push("use uuid::Uuid;".to_string());
-    }
-    if needs_json {
-        lines.push("use serde_json;".to_string());
-    }
+fn generate_drop_function(function: &Function) -> String {
+    format!(
+        "DROP FUNCTION IF EXISTS \"{}\"({})",
+        function.name,
+        identity_arguments(&function.arguments)
+    )
+}
 
-    let mut sorted_relation_imports: Vec<_> = relation_imports.into_iter().collect();
-    sorted_relation_imports.sort();
-    for rel_table in &sorted_relation_imports {
-        lines.push(format!(
-            "use super::{}::{};",
-            rel_table,
-            to_struct_name(rel_table)
-        ));
+fn generate_create_function(qualified_name: &str, function: &Function) -> String {
+    let mut attributes = function.volatility.to_uppercase();
+    if function.strict {
+        attributes.push_str(" STRICT");
     }
-
-    lines.push(String::new());
-
-    // Struct definition
-    lines.push("#[derive(Debug, Clone, FromRow)]".to_string());
-    lines.push(format!("pub struct {} {{", struct_name));
-
-    // Column fields
-    for col in &table.column {
-        let rust_type = pg_type_to_rust(&col.data_type, col.is_nullable);
-        lines.push(format!("    pub {}: {},", col.name, rust_type));
-    }
-
-    // belongs_to relation fields
-    if let Some(bt_relations) = belongs_to.get(&table.name) {
-        if !bt_relations.is_empty() {
-            lines.push(String::new());
-            lines.push("    // belongs_to relations".to_string());
-            for (fk_column, parent_table, _) in bt_relations {
-                if all_tables.contains(&parent_table.as_str()) {
-                    let field_name = fk_column.trim_end_matches("_id");
-                    let parent_struct = to_struct_name(parent_table);
-                    lines.push("    #[sqlx(skip)]".to_string());
-                    lines.push(format!(
-                        "    pub {}: Option<{}>,",
-                        field_name, parent_struct
-                    ));
-                }
-            }
-        }
+    if function.security_definer {
+        attributes.push_str(" SECURITY DEFINER");
     }
-
-    // has_many relation fields
-    if let Some(hm_relations) = has_many.get(&table.name) {
-        if !hm_relations.is_empty() {
-            lines.push(String::new());
-            lines.push("    // has_many relations".to_string());
-            for child_table in hm_relations {
-                if all_tables.contains(&child_table.as_str()) {
-                    let child_struct = to_struct_name(child_table);
-                    let field_name = to_plural(child_table);
-                    lines.push("    #[sqlx(skip)]".to_string());
-                    lines.push(format!(
-                        "    pub {}: Option<Vec<{}>>,",
-                        field_name, child_struct
-                    ));
-                }
-            }
-        }
+    if let Some(search_path) = &function.search_path {
+        attributes.push_str(&format!(" SET search_path = {}", search_path));
     }
 
-    lines.push("}".to_string());
-    lines.push(String::new());
-
-    lines.join("\n")
+    format!(
+        "CREATE OR REPLACE FUNCTION {}({}) RETURNS {} LANGUAGE {} {} AS $function$\n{}\n$function$",
+        qualified_name,
+        function.arguments,
+        function.returns,
+        function.language,
+        attributes,
+        function.body.trim()
+    )
 }
 
-fn generate_mod_file(table_names: &[&str]) -> String {
-    let mut lines = Vec::new();
-
-    for name in table_names {
-        lines.push(format!("mod {};", name));
-    }
-
-    lines.push(String::new());
-
-    for name in table_names {
-        lines.push(format!("pub use {}::{};", name, to_struct_name(name)));
-    }
+fn generate_create_trigger(trigger: &Trigger) -> String {
+    let events: Vec<String> = trigger.events.iter().map(|e| e.to_uppercase()).collect();
+    let when = match &trigger.when {
+        Some(condition) => format!(" WHEN ({})", strip_outer_parens(condition)),
+        None => String::new(),
+    };
 
-    lines.push(String::new());
+    let arguments: Vec<String> = trigger.arguments.iter().map(|a| quote_literal(a)).collect();
 
-    lines.join("\n")
+    format!(
+        "CREATE TRIGGER \"{}\" {} {} ON \"{}\" FOR EACH {}{} EXECUTE FUNCTION \"{}\"({})",
+        trigger.name,
+        trigger.timing.to_uppercase(),
+        events.join(" OR "),
+        trigger.table,
+        trigger.for_each.to_uppercase(),
+        when,
+        trigger.function,
+        arguments.join(", ")
+    )
 }
 
-fn pg_type_to_rust(data_type: &str, is_nullable: bool) -> String {
-    let base_type = match data_type {
-        "integer" | "int" | "int4" => "i32",
-        "bigint" | "int8" => "i64",
-        "smallint" | "int2" => "i16",
-        "text" | "character varying" | "varchar" | "char" | "character" => "String",
-        "boolean" | "bool" => "bool",
-        "real" | "float4" => "f32",
-        "double precision" | "float8" => "f64",
-        "timestamp without time zone" | "timestamp" => "chrono::NaiveDateTime",
-        "timestamp with time zone" | "timestamptz" => "chrono::DateTime<chrono::Utc>",
-        "date" => "chrono::NaiveDate",
-        "time" | "time without time zone" => "chrono::NaiveTime",
-        "numeric" | "decimal" => "Decimal",
-        "uuid" => "Uuid",
-        "json" | "jsonb" => "serde_json::Value",
-        "bytea" => "Vec<u8>",
-        _ => "String",
-    };
-
-    if is_nullable {
-        format!("Option<{}>", base_type)
-    } else {
-        base_type.to_string()
-    }
+fn generate_drop_trigger(trigger: &Trigger) -> String {
+    format!(
+        "DROP TRIGGER IF EXISTS \"{}\" ON \"{}\"",
+        trigger.name, trigger.table
+    )
 }
 
-fn to_struct_name(name: &str) -> String {
-    name.split('_')
-        .map(|part| {
-            let mut chars = part.chars();
-            match chars.next() {
-                None => String::new(),
-                Some(first) => first.to_uppercase().chain(chars).collect(),
-            }
-        })
-        .collect()
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canonical_type_matches_format_type() {
+        assert_eq!(canonical_type("int4"), "integer");
+        assert_eq!(canonical_type("BIGSERIAL"), "bigint");
+        assert_eq!(canonical_type("varchar(255)"), "character varying(255)");
+        assert_eq!(canonical_type("decimal(10, 2)"), "numeric(10,2)");
+        assert_eq!(canonical_type("timestamptz"), "timestamp with time zone");
+        assert_eq!(canonical_type("timestamp(3) with time zone"), "timestamp(3) with time zone");
+        assert_eq!(canonical_type("timestamptz(3)"), "timestamp(3) with time zone");
+        assert_eq!(canonical_type("time"), "time without time zone");
+        assert_eq!(canonical_type("char"), "character(1)");
+        assert_eq!(canonical_type("int[]"), "integer[]");
+        assert_eq!(canonical_type("percent"), "percent");
+    }
 
-fn to_plural(name: &str) -> String {
-    if name.ends_with('s') {
-        format!("{}es", name)
-    } else if name.ends_with('y') {
-        format!("{}ies", name.trim_end_matches('y'))
-    } else {
-        format!("{}s", name)
+    #[test]
+    fn normalize_sql_keeps_literal_case() {
+        assert_eq!(normalize_sql("SELECT  1 ;"), "select 1");
+        assert_ne!(normalize_sql("kind = 'X'"), normalize_sql("kind = 'x'"));
+        assert_eq!(normalize_sql("WHERE kind = 'a  b'"), "where kind = 'a  b'");
     }
 }
@@ -1,24 +1,104 @@
-use anyhow::Result;
-use serde::{Deserialize, Serialize};
+use anyhow::{bail, Context, Result};
+use schemars::generate::SchemaSettings;
+use schemars::JsonSchema;
+use serde::de::{MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use std::fs;
+use std::path::{Path, PathBuf};
 
-use crate::schema::{Column, ForeignKey, Index, Schema};
+use crate::schema::{
+    serial_default, Column, CompositeType, Domain, ForeignKey, Function, Grant, Index, Partition,
+    PartitionBy, Policy, Schema, Trigger,
+};
+use crate::validate::SourceMap;
 
 // ============ Type Definitions ============
 
-#[derive(Serialize, Deserialize)]
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
 pub struct TomlSchema {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extensions: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub domain: Vec<Domain>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub composite_type: Vec<CompositeType>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mixin: BTreeMap<String, TomlMixin>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub table: Vec<TomlTable>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub view: Vec<TomlView>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub function: Vec<Function>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trigger: Vec<Trigger>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub grant: Vec<Grant>,
+    #[serde(skip)]
+    pub source_map: SourceMap,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct TomlTable {
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub rls_enabled: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub force_rls: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partition_by: Option<PartitionBy>,
+    /// Mixins whose columns are appended after the table's own
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Shorthand columns such as `email = "text not null unique"`
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_shorthands",
+        deserialize_with = "deserialize_shorthands"
+    )]
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub columns: Vec<(String, String)>,
+    #[serde(default)]
     pub column: Vec<Column>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub foreign_key: Vec<ForeignKey>,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub index: Vec<Index>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub policy: Vec<Policy>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub partition: Vec<Partition>,
+}
+
+/// Columns shared between tables through `include = ["name"]`
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
+pub struct TomlMixin {
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_shorthands",
+        deserialize_with = "deserialize_shorthands"
+    )]
+    #[schemars(with = "BTreeMap<String, String>")]
+    pub columns: Vec<(String, String)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column: Vec<Column>,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct TomlView {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub materialized: bool,
+    pub definition: String,
+    /// Output columns, only used by codegen
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub column: Vec<Column>,
 }
 
 // ============ TomlSchema Methods ============
@@ -27,30 +107,594 @@ impl TomlSchema {
     /// Read a TomlSchema from a TOML file
     pub fn from_file(path: &str) -> Result<Self> {
         let toml_str = fs::read_to_string(path)?;
-        let toml_schema: TomlSchema = toml::from_str(&toml_str)?;
+        let mut toml_schema: TomlSchema = toml::from_str(&toml_str)?;
+        toml_schema.source_map.add_file(Path::new(path), &toml_str, false)?;
         Ok(toml_schema)
     }
 
-    /// Write this TomlSchema to a file
+    /// Read a TomlSchema from a single file or a split schema directory.
+    /// A file whose directory contains `tables/` is treated as part of that
+    /// directory, so the default `schema/schema.toml` path keeps working.
+    pub fn from_path(path: &str) -> Result<Self> {
+        match schema_dir(Path::new(path)) {
+            Some(dir) => Self::from_dir(dir),
+            None => Self::from_file(path),
+        }
+    }
+
+    /// The files `from_path` would read, each flagged when it holds a
+    /// single table from a split schema's `tables/` directory
+    pub fn source_files(path: &str) -> Result<Vec<(PathBuf, bool)>> {
+        let Some(dir) = schema_dir(Path::new(path)) else {
+            return Ok(vec![(PathBuf::from(path), false)]);
+        };
+
+        let mut files: Vec<(PathBuf, bool)> = toml_files(dir)?.into_iter().map(|f| (f, false)).collect();
+        let tables_dir = dir.join(TABLES_DIR);
+        if tables_dir.is_dir() {
+            files.extend(toml_files(&tables_dir)?.into_iter().map(|f| (f, true)));
+        }
+
+        Ok(files)
+    }
+
+    /// Read a split schema directory: every `*.toml` at the top level holds
+    /// shared definitions and every `tables/*.toml` holds a single table
+    pub fn from_dir(dir: &Path) -> Result<Self> {
+        let mut merged = TomlSchema::default();
+        let mut sources = SourceTracker::default();
+
+        for file in toml_files(dir)? {
+            let toml_str = fs::read_to_string(&file)?;
+            let fragment: TomlSchema = toml::from_str(&toml_str)
+                .with_context(|| format!("Failed to parse {}", file.display()))?;
+            merged.merge(fragment, &file, &mut sources)?;
+            merged.source_map.add_file(&file, &toml_str, false)?;
+        }
+
+        let tables_dir = dir.join(TABLES_DIR);
+        if tables_dir.is_dir() {
+            for file in toml_files(&tables_dir)? {
+                let toml_str = fs::read_to_string(&file)?;
+                let table: TomlTable = toml::from_str(&toml_str)
+                    .with_context(|| format!("Failed to parse {}", file.display()))?;
+                let fragment = TomlSchema {
+                    table: vec![table],
+                    ..Default::default()
+                };
+                merged.merge(fragment, &file, &mut sources)?;
+                merged.source_map.add_file(&file, &toml_str, true)?;
+            }
+        }
+
+        Ok(merged)
+    }
+
+    /// Write this TomlSchema to a file. Files in a split schema directory
+    /// are refused, since the directory's other files would be loaded
+    /// alongside the complete schema and define everything twice.
     pub fn write_file(&self, path: &str) -> Result<()> {
+        if let Some(dir) = schema_dir(Path::new(path)) {
+            bail!("{} is part of the split schema directory {}", path, dir.display());
+        }
         let toml_string = toml::to_string_pretty(self)?;
         fs::write(path, toml_string)?;
         Ok(())
     }
+
+    /// JSON Schema describing a schema file, for editor validation and completion
+    pub fn json_schema() -> Result<String> {
+        json_schema_for::<TomlSchema>()
+    }
+
+    /// Write this TomlSchema as a split directory: one file per table under
+    /// `tables/` and everything else in `schema.toml`. Table files for tables
+    /// that no longer exist are removed.
+    pub fn write_dir(self, dir: &str) -> Result<()> {
+        let dir = Path::new(dir);
+        let tables_dir = dir.join(TABLES_DIR);
+        fs::create_dir_all(&tables_dir)?;
+
+        let mut written = Vec::new();
+
+        for table in &self.table {
+            let file = tables_dir.join(format!("{}.toml", table.name));
+            fs::write(&file, toml::to_string_pretty(table)?)?;
+            written.push(file);
+        }
+
+        for file in toml_files(&tables_dir)? {
+            if !written.contains(&file) {
+                fs::remove_file(&file)?;
+            }
+        }
+
+        let shared = TomlSchema {
+            table: Vec::new(),
+            ..self
+        };
+        fs::write(dir.join("schema.toml"), toml::to_string_pretty(&shared)?)?;
+
+        Ok(())
+    }
+
+    /// Append a fragment loaded from `file`, rejecting names that were
+    /// already defined by an earlier file
+    fn merge(&mut self, fragment: TomlSchema, file: &Path, sources: &mut SourceTracker) -> Result<()> {
+        for extension in &fragment.extensions {
+            sources.claim("extension", extension, file)?;
+        }
+        for domain in &fragment.domain {
+            sources.claim("type", &domain.name, file)?;
+        }
+        for composite in &fragment.composite_type {
+            sources.claim("type", &composite.name, file)?;
+        }
+        for mixin in fragment.mixin.keys() {
+            sources.claim("mixin", mixin, file)?;
+        }
+        for table in &fragment.table {
+            sources.claim("table or view", &table.name, file)?;
+        }
+        for view in &fragment.view {
+            sources.claim("table or view", &view.name, file)?;
+        }
+        for function in &fragment.function {
+            let signature = format!("{}({})", function.name, function.arguments);
+            sources.claim("function", &signature, file)?;
+        }
+        for trigger in &fragment.trigger {
+            let qualified = format!("{}.{}", trigger.table, trigger.name);
+            sources.claim("trigger", &qualified, file)?;
+        }
+
+        self.extensions.extend(fragment.extensions);
+        self.domain.extend(fragment.domain);
+        self.composite_type.extend(fragment.composite_type);
+        self.mixin.extend(fragment.mixin);
+        self.table.extend(fragment.table);
+        self.view.extend(fragment.view);
+        self.function.extend(fragment.function);
+        self.trigger.extend(fragment.trigger);
+        self.grant.extend(fragment.grant);
+
+        Ok(())
+    }
+
     pub fn from_schema(schema: Schema) -> Self {
         let mut tables: Vec<TomlTable> = schema
             .tables
             .into_iter()
             .map(|(name, table)| TomlTable {
                 name,
+                comment: table.comment,
+                rls_enabled: table.rls_enabled,
+                force_rls: table.force_rls,
+                partition_by: table.partition_by,
+                include: Vec::new(),
+                columns: Vec::new(),
                 column: table.columns,
                 foreign_key: table.foreign_keys,
                 index: table.indexes,
+                policy: table.policies,
+                partition: table.partitions,
             })
             .collect();
 
         tables.sort_by(|a, b| a.name.cmp(&b.name));
 
-        TomlSchema { table: tables }
+        let mut views: Vec<TomlView> = schema
+            .views
+            .into_iter()
+            .map(|(name, view)| TomlView {
+                name,
+                materialized: view.materialized,
+                definition: view.definition,
+                column: view.columns,
+            })
+            .collect();
+
+        views.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut functions = schema.functions;
+        functions.sort_by(|a, b| (&a.name, &a.arguments).cmp(&(&b.name, &b.arguments)));
+
+        let mut triggers = schema.triggers;
+        triggers.sort_by(|a, b| (&a.table, &a.name).cmp(&(&b.table, &b.name)));
+
+        let mut extensions = schema.extensions;
+        extensions.sort();
+
+        let mut domains = schema.domains;
+        domains.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut composite_types = schema.composite_types;
+        composite_types.sort_by(|a, b| a.name.cmp(&b.name));
+
+        TomlSchema {
+            extensions,
+            domain: domains,
+            composite_type: composite_types,
+            mixin: BTreeMap::new(),
+            table: tables,
+            view: views,
+            function: functions,
+            trigger: triggers,
+            grant: schema.grants,
+            source_map: SourceMap::default(),
+        }
+    }
+}
+
+// ============ TomlTable Methods ============
+
+impl TomlTable {
+    /// JSON Schema describing a single table file in a split schema's `tables/`
+    pub fn json_schema() -> Result<String> {
+        json_schema_for::<TomlTable>()
     }
-}
\ No newline at end of file
+
+    /// Expand shorthand columns and included mixins into canonical columns,
+    /// adding the indexes and foreign keys the shorthand implies. Shorthand
+    /// columns come first, then full `[[column]]` entries, then mixins in
+    /// `include` order.
+    pub(crate) fn expand(&mut self, mixins: &BTreeMap<String, TomlMixin>) -> Result<()> {
+        let mut sources = vec![(&self.columns, std::mem::take(&mut self.column))];
+        for name in &self.include {
+            let mixin = mixins.get(name).with_context(|| {
+                format!("Table `{}` includes unknown mixin `{}`", self.name, name)
+            })?;
+            sources.push((&mixin.columns, mixin.column.clone()));
+        }
+
+        let mut columns = Vec::new();
+        let mut indexes = Vec::new();
+        let mut foreign_keys = Vec::new();
+
+        for (shorthands, full_columns) in sources {
+            for (name, spec) in shorthands {
+                let shorthand = parse_column_shorthand(name, spec)
+                    .with_context(|| format!("Invalid column `{}.{}`", self.name, name))?;
+
+                if shorthand.primary_key {
+                    indexes.push(Index {
+                        name: format!("{}_pkey", self.name),
+                        columns: vec![name.clone()],
+                        is_unique: true,
+                        index_type: "btree".to_string(),
+                    });
+                }
+                if shorthand.unique {
+                    indexes.push(Index {
+                        name: format!("{}_{}_key", self.name, name),
+                        columns: vec![name.clone()],
+                        is_unique: true,
+                        index_type: "btree".to_string(),
+                    });
+                }
+                if let Some((referenced_table, referenced_column)) = shorthand.references {
+                    foreign_keys.push(ForeignKey {
+                        column: name.clone(),
+                        referenced_table,
+                        referenced_column,
+                    });
+                }
+                columns.push(shorthand.column);
+            }
+            columns.extend(full_columns);
+        }
+
+        for column in &mut columns {
+            expand_serial(&self.name, column);
+        }
+
+        self.column = columns;
+        for idx in indexes {
+            if !self.index.iter().any(|i| i.name == idx.name) {
+                self.index.push(idx);
+            }
+        }
+        self.foreign_key.extend(foreign_keys);
+
+        Ok(())
+    }
+}
+
+/// Draft 7, which taplo and most editor integrations understand
+fn json_schema_for<T: JsonSchema>() -> Result<String> {
+    let schema = SchemaSettings::draft07().into_generator().into_root_schema_for::<T>();
+    Ok(serde_json::to_string_pretty(&schema)?)
+}
+
+// ============ Column Shorthand ============
+
+/// A column parsed from `type [not null | null] [primary key] [unique]
+/// [default expr] [references table(column)]`
+struct ColumnShorthand {
+    column: Column,
+    primary_key: bool,
+    unique: bool,
+    references: Option<(String, String)>,
+}
+
+const SHORTHAND_KEYWORDS: &[&str] = &["not", "null", "primary", "unique", "default", "references"];
+
+fn parse_column_shorthand(name: &str, spec: &str) -> Result<ColumnShorthand> {
+    let tokens = shorthand_tokens(spec);
+    let is_keyword = |token: &str| SHORTHAND_KEYWORDS.contains(&token.to_lowercase().as_str());
+
+    let type_end = tokens.iter().position(|t| is_keyword(t)).unwrap_or(tokens.len());
+    if type_end == 0 {
+        bail!("`{}` does not start with a type", spec);
+    }
+
+    let mut shorthand = ColumnShorthand {
+        column: Column {
+            name: name.to_string(),
+            data_type: tokens[..type_end].join(" "),
+            is_nullable: true,
+            default: None,
+            comment: None,
+        },
+        primary_key: false,
+        unique: false,
+        references: None,
+    };
+
+    let mut rest = &tokens[type_end..];
+    while let Some((token, tail)) = rest.split_first() {
+        rest = tail;
+        match token.to_lowercase().as_str() {
+            "not" if rest.first().is_some_and(|t| t.eq_ignore_ascii_case("null")) => {
+                shorthand.column.is_nullable = false;
+                rest = &rest[1..];
+            }
+            "null" => shorthand.column.is_nullable = true,
+            "primary" if rest.first().is_some_and(|t| t.eq_ignore_ascii_case("key")) => {
+                shorthand.primary_key = true;
+                shorthand.column.is_nullable = false;
+                rest = &rest[1..];
+            }
+            "unique" => shorthand.unique = true,
+            "default" => {
+                // The expression runs to the next modifier, and is at least one token
+                let len = rest.iter().skip(1).position(|t| is_keyword(t)).map_or(rest.len(), |i| i + 1);
+                if len == 0 {
+                    bail!("`default` needs an expression");
+                }
+                shorthand.column.default = Some(rest[..len].join(" "));
+                rest = &rest[len..];
+            }
+            "references" => {
+                let Some((target, tail)) = rest.split_first() else {
+                    bail!("`references` needs a table");
+                };
+                rest = tail;
+                let mut target = target.to_string();
+                if let Some(columns) = rest.first().filter(|t| t.starts_with('(')) {
+                    target.push_str(columns);
+                    rest = &rest[1..];
+                }
+                shorthand.references = Some(match target.split_once('(') {
+                    Some((table, column)) => (
+                        table.trim().to_string(),
+                        column.trim_end_matches(')').trim().to_string(),
+                    ),
+                    None => (target, "id".to_string()),
+                });
+            }
+            _ => bail!("Unknown column modifier `{}`", token),
+        }
+    }
+
+    Ok(shorthand)
+}
+
+/// Rewrite a serial column the way Postgres stores it: an integer type that
+/// is NOT NULL and defaults to the next value of `{table}_{column}_seq`
+fn expand_serial(table: &str, column: &mut Column) {
+    let integer_type = match column.data_type.trim().to_lowercase().as_str() {
+        "serial" | "serial4" => "integer",
+        "bigserial" | "serial8" => "bigint",
+        "smallserial" | "serial2" => "smallint",
+        _ => return,
+    };
+
+    column.data_type = integer_type.to_string();
+    column.is_nullable = false;
+    if column.default.is_none() {
+        column.default = Some(serial_default(table, &column.name));
+    }
+}
+
+/// Split on whitespace outside of quotes and parentheses, so
+/// `numeric(12, 2)` and `default 'a b'` stay whole
+fn shorthand_tokens(spec: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = None;
+    let mut depth = 0usize;
+    let mut in_quote = false;
+
+    for (i, c) in spec.char_indices() {
+        match c {
+            '\'' => in_quote = !in_quote,
+            '(' if !in_quote => depth += 1,
+            ')' if !in_quote => depth = depth.saturating_sub(1),
+            c if c.is_whitespace() && !in_quote && depth == 0 => {
+                if let Some(s) = start.take() {
+                    tokens.push(&spec[s..i]);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        start.get_or_insert(i);
+    }
+    if let Some(s) = start {
+        tokens.push(&spec[s..]);
+    }
+
+    tokens
+}
+
+/// Shorthand columns are a TOML table, kept in document order
+fn serialize_shorthands<S: Serializer>(pairs: &[(String, String)], serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(pairs.iter().map(|(k, v)| (k, v)))
+}
+
+fn deserialize_shorthands<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<(String, String)>, D::Error> {
+    struct PairsVisitor;
+
+    impl<'de> Visitor<'de> for PairsVisitor {
+        type Value = Vec<(String, String)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a table of column definitions")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut pairs = Vec::new();
+            while let Some(pair) = map.next_entry()? {
+                pairs.push(pair);
+            }
+            Ok(pairs)
+        }
+    }
+
+    deserializer.deserialize_map(PairsVisitor)
+}
+
+// ============ Split Directory Helpers ============
+
+const TABLES_DIR: &str = "tables";
+
+/// The split schema directory `path` refers to, if any
+fn schema_dir(path: &Path) -> Option<&Path> {
+    if path.is_dir() {
+        return Some(path);
+    }
+    path.parent().filter(|parent| parent.join(TABLES_DIR).is_dir())
+}
+
+/// Remembers which file defined each name while merging a split schema
+#[derive(Default)]
+struct SourceTracker {
+    seen: HashMap<(&'static str, String), PathBuf>,
+}
+
+impl SourceTracker {
+    fn claim(&mut self, kind: &'static str, name: &str, file: &Path) -> Result<()> {
+        if let Some(previous) = self.seen.get(&(kind, name.to_string())) {
+            bail!(
+                "Duplicate {} `{}` defined in {} and {}",
+                kind,
+                name,
+                previous.display(),
+                file.display()
+            );
+        }
+        self.seen.insert((kind, name.to_string()), file.to_path_buf());
+        Ok(())
+    }
+}
+
+/// `*.toml` files directly inside `dir`, sorted by name
+fn toml_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))? {
+        let path = entry?.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "toml") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(spec: &str) -> ColumnShorthand {
+        parse_column_shorthand("col", spec).unwrap()
+    }
+
+    #[test]
+    fn shorthand_type_runs_to_first_modifier() {
+        let shorthand = parse("numeric(12, 2) not null default 0");
+        assert_eq!(shorthand.column.data_type, "numeric(12, 2)");
+        assert!(!shorthand.column.is_nullable);
+        assert_eq!(shorthand.column.default.as_deref(), Some("0"));
+
+        assert_eq!(parse("timestamp with time zone").column.data_type, "timestamp with time zone");
+        assert!(parse("text").column.is_nullable);
+    }
+
+    #[test]
+    fn shorthand_default_keeps_quoted_spaces() {
+        let shorthand = parse("text default 'a b' not null");
+        assert_eq!(shorthand.column.default.as_deref(), Some("'a b'"));
+        assert!(!shorthand.column.is_nullable);
+
+        let shorthand = parse("timestamptz default now() unique");
+        assert_eq!(shorthand.column.default.as_deref(), Some("now()"));
+        assert!(shorthand.unique);
+    }
+
+    #[test]
+    fn shorthand_primary_key_implies_not_null() {
+        let shorthand = parse("bigint null primary key");
+        assert!(shorthand.primary_key);
+        assert!(!shorthand.column.is_nullable);
+    }
+
+    #[test]
+    fn shorthand_references() {
+        assert_eq!(
+            parse("bigint references users").references,
+            Some(("users".to_string(), "id".to_string()))
+        );
+        assert_eq!(
+            parse("text references accounts(email)").references,
+            Some(("accounts".to_string(), "email".to_string()))
+        );
+        assert_eq!(
+            parse("text references accounts (email) not null").references,
+            Some(("accounts".to_string(), "email".to_string()))
+        );
+    }
+
+    #[test]
+    fn shorthand_rejects_malformed_specs() {
+        assert!(parse_column_shorthand("col", "").is_err());
+        assert!(parse_column_shorthand("col", "not null").is_err());
+        assert!(parse_column_shorthand("col", "text default").is_err());
+        assert!(parse_column_shorthand("col", "text references").is_err());
+        assert!(parse_column_shorthand("col", "text primary").is_err());
+    }
+
+    #[test]
+    fn serial_columns_expand_like_postgres() {
+        let mut table: TomlTable = toml::from_str(
+            r#"
+            name = "users"
+            columns = { id = "bigserial primary key", Rank = "serial" }
+            column = [{ name = "n", data_type = "smallserial", is_nullable = true }]
+            "#,
+        )
+        .unwrap();
+        table.expand(&BTreeMap::new()).unwrap();
+
+        let column = |name: &str| table.column.iter().find(|c| c.name == name).unwrap();
+        assert_eq!(column("id").data_type, "bigint");
+        assert_eq!(column("id").default.as_deref(), Some("nextval('users_id_seq'::regclass)"));
+        assert_eq!(column("Rank").data_type, "integer");
+        assert_eq!(
+            column("Rank").default.as_deref(),
+            Some("nextval('\"users_Rank_seq\"'::regclass)")
+        );
+        assert_eq!(column("n").data_type, "smallint");
+        assert!(!column("n").is_nullable);
+        assert!(table.index.iter().any(|i| i.name == "users_pkey"));
+    }
+}
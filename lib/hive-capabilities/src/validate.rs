@@ -0,0 +1,439 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+use crate::schema::{Schema, Table};
+
+// ============ Type Definitions ============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Option<SourceLocation>,
+}
+
+/// A 1-based line and column in a schema file
+#[derive(Debug, Clone)]
+pub struct SourceLocation {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// Locations of schema items in the TOML files they were read from, keyed
+/// by item path such as `table.users.column[2].data_type`
+#[derive(Debug, Clone, Default)]
+pub struct SourceMap {
+    locations: HashMap<String, SourceLocation>,
+    duplicates: Vec<Diagnostic>,
+    trigger_count: usize,
+    grant_count: usize,
+}
+
+// ============ Schema Validation ============
+
+impl Schema {
+    /// Check references, names and types, returning every problem found
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = self.source_map.duplicates.clone();
+        let known_types = self.known_types();
+
+        let mut table_names: Vec<&String> = self.tables.keys().collect();
+        table_names.sort();
+
+        for table_name in table_names {
+            let table = &self.tables[table_name];
+            let at = |key: &str| self.source_map.locate(&format!("table.{}{}", table_name, key));
+
+            let mut seen_columns = HashSet::new();
+            for (i, col) in table.columns.iter().enumerate() {
+                if !seen_columns.insert(col.name.as_str()) {
+                    diagnostics.push(Diagnostic::error(
+                        format!("Duplicate column `{}` in table `{}`", col.name, table_name),
+                        at(&format!(".column[{}]", i)),
+                    ));
+                }
+
+                if !known_types.contains(base_type_name(&col.data_type).as_str()) {
+                    diagnostics.push(Diagnostic::warning(
+                        format!(
+                            "Unknown type `{}` for `{}.{}`; it must already exist in the database",
+                            col.data_type, table_name, col.name
+                        ),
+                        at(&format!(".column[{}].data_type", i)),
+                    ));
+                }
+            }
+
+            for (i, fk) in table.foreign_keys.iter().enumerate() {
+                let key = format!(".foreign_key[{}]", i);
+
+                if !has_column(table, &fk.column) {
+                    diagnostics.push(Diagnostic::error(
+                        format!(
+                            "Foreign key column `{}` does not exist in table `{}`",
+                            fk.column, table_name
+                        ),
+                        at(&format!("{}.column", key)),
+                    ));
+                }
+
+                match self.tables.get(&fk.referenced_table) {
+                    None => diagnostics.push(Diagnostic::error(
+                        format!(
+                            "Foreign key `{}.{}` references missing table `{}`",
+                            table_name, fk.column, fk.referenced_table
+                        ),
+                        at(&format!("{}.referenced_table", key)),
+                    )),
+                    Some(parent) if !has_column(parent, &fk.referenced_column) => {
+                        diagnostics.push(Diagnostic::error(
+                            format!(
+                                "Foreign key `{}.{}` references missing column `{}.{}`",
+                                table_name, fk.column, fk.referenced_table, fk.referenced_column
+                            ),
+                            at(&format!("{}.referenced_column", key)),
+                        ))
+                    }
+                    Some(_) => {}
+                }
+            }
+
+            for (i, idx) in table.indexes.iter().enumerate() {
+                for column in &idx.columns {
+                    if !has_column(table, column) {
+                        diagnostics.push(Diagnostic::error(
+                            format!(
+                                "Index `{}` uses missing column `{}` of table `{}`",
+                                idx.name, column, table_name
+                            ),
+                            at(&format!(".index[{}].columns", i)),
+                        ));
+                    }
+                }
+            }
+
+            if let Some(partition_by) = &table.partition_by {
+                for column in &partition_by.columns {
+                    if !has_column(table, column) {
+                        diagnostics.push(Diagnostic::error(
+                            format!(
+                                "Partition key uses missing column `{}` of table `{}`",
+                                column, table_name
+                            ),
+                            at(".partition_by.columns"),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let function_names: HashSet<&str> = self.functions.iter().map(|f| f.name.as_str()).collect();
+
+        for (i, trigger) in self.triggers.iter().enumerate() {
+            let at = |key: &str| self.source_map.locate(&format!("trigger[{}]{}", i, key));
+
+            if !self.tables.contains_key(&trigger.table) && !self.views.contains_key(&trigger.table) {
+                diagnostics.push(Diagnostic::error(
+                    format!(
+                        "Trigger `{}` is on missing table `{}`",
+                        trigger.name, trigger.table
+                    ),
+                    at(".table"),
+                ));
+            }
+
+            if !function_names.contains(trigger.function.as_str()) {
+                diagnostics.push(Diagnostic::warning(
+                    format!(
+                        "Trigger `{}` calls `{}`, which is not defined in the schema",
+                        trigger.name, trigger.function
+                    ),
+                    at(".function"),
+                ));
+            }
+        }
+
+        for (i, grant) in self.grants.iter().enumerate() {
+            for table in &grant.tables {
+                if table != "*" && !self.tables.contains_key(table) && !self.views.contains_key(table) {
+                    diagnostics.push(Diagnostic::error(
+                        format!("Grant to `{}` names missing table `{}`", grant.role, table),
+                        self.source_map.locate(&format!("grant[{}].tables", i)),
+                    ));
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    /// Built-in types plus the domains, composite types and extension types
+    /// this schema defines
+    fn known_types(&self) -> HashSet<String> {
+        let mut known: HashSet<String> = BUILTIN_TYPES.iter().map(|t| t.to_string()).collect();
+
+        known.extend(self.domains.iter().map(|d| d.name.clone()));
+        known.extend(self.composite_types.iter().map(|c| c.name.clone()));
+
+        for extension in &self.extensions {
+            if let Some((_, types)) = EXTENSION_TYPES.iter().find(|(name, _)| name == extension) {
+                known.extend(types.iter().map(|t| t.to_string()));
+            }
+        }
+
+        known
+    }
+}
+
+fn has_column(table: &Table, name: &str) -> bool {
+    table.columns.iter().any(|c| c.name == name)
+}
+
+/// Lowercased type name without modifiers or array brackets,
+/// so `numeric(12,2)` and `text[]` check as `numeric` and `text`
+fn base_type_name(data_type: &str) -> String {
+    let mut name = data_type.trim().to_lowercase();
+    if let (Some(start), Some(end)) = (name.find('('), name.find(')'))
+        && start < end
+    {
+        name = format!("{}{}", name[..start].trim_end(), &name[end + 1..]);
+    }
+    name.trim_end_matches("[]").trim().to_string()
+}
+
+const BUILTIN_TYPES: &[&str] = &[
+    "smallint", "integer", "int", "bigint", "int2", "int4", "int8",
+    "smallserial", "serial", "bigserial", "serial2", "serial4", "serial8",
+    "real", "double precision", "float", "float4", "float8", "numeric", "decimal", "money",
+    "text", "character varying", "varchar", "character", "char", "bpchar", "name",
+    "boolean", "bool", "bytea", "uuid", "json", "jsonb", "xml",
+    "timestamp", "timestamp without time zone", "timestamp with time zone", "timestamptz",
+    "date", "time", "time without time zone", "time with time zone", "timetz", "interval",
+    "inet", "cidr", "macaddr", "macaddr8", "bit", "bit varying", "varbit",
+    "point", "line", "lseg", "box", "path", "polygon", "circle",
+    "tsvector", "tsquery", "oid", "pg_lsn", "txid_snapshot", "pg_snapshot",
+    "int4range", "int8range", "numrange", "tsrange", "tstzrange", "daterange",
+    "int4multirange", "int8multirange", "nummultirange", "tsmultirange", "tstzmultirange",
+    "datemultirange",
+    // information_schema reports arrays this way
+    "array",
+];
+
+const EXTENSION_TYPES: &[(&str, &[&str])] = &[
+    ("citext", &["citext"]),
+    ("hstore", &["hstore"]),
+    ("ltree", &["ltree", "lquery", "ltxtquery"]),
+    ("cube", &["cube"]),
+    ("isn", &["isbn", "isbn13", "ismn", "ismn13", "issn", "issn13", "ean13", "upc"]),
+    ("postgis", &["geometry", "geography", "box2d", "box3d"]),
+    ("vector", &["vector", "halfvec", "sparsevec"]),
+];
+
+// ============ Diagnostic Methods ============
+
+impl Diagnostic {
+    fn error(message: String, location: Option<SourceLocation>) -> Self {
+        Diagnostic { severity: Severity::Error, message, location }
+    }
+
+    fn warning(message: String, location: Option<SourceLocation>) -> Self {
+        Diagnostic { severity: Severity::Warning, message, location }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match &self.location {
+            Some(loc) => write!(
+                f,
+                "{}:{}:{}: {}: {}",
+                loc.file.display(),
+                loc.line,
+                loc.column,
+                severity,
+                self.message
+            ),
+            None => write!(f, "{}: {}", severity, self.message),
+        }
+    }
+}
+
+// ============ SourceMap Methods ============
+
+impl SourceMap {
+    /// Record item locations from a schema file. `single_table` files hold
+    /// one table at the top level, as in a split schema's `tables/` directory.
+    pub(crate) fn add_file(&mut self, file: &Path, text: &str, single_table: bool) -> Result<()> {
+        let root = DeTable::parse(text)
+            .with_context(|| format!("Failed to parse {}", file.display()))?;
+        let mut recorder = Recorder { map: self, file, text };
+
+        if single_table {
+            recorder.record_table(root.get_ref());
+            return Ok(());
+        }
+
+        for table in array_items(root.get_ref(), "table") {
+            if let DeValue::Table(table) = table.get_ref() {
+                recorder.record_table(table);
+            }
+        }
+
+        for view in array_items(root.get_ref(), "view") {
+            if let Some(name) = view.get_ref().get("name") {
+                let key = format!("view.{}", name.get_ref().as_str().unwrap_or_default());
+                recorder.record(&key, name);
+            }
+        }
+
+        for trigger in array_items(root.get_ref(), "trigger") {
+            let key = format!("trigger[{}]", recorder.map.trigger_count);
+            recorder.map.trigger_count += 1;
+            recorder.record_fields(&key, trigger.get_ref(), &["table", "function"]);
+        }
+
+        for grant in array_items(root.get_ref(), "grant") {
+            let key = format!("grant[{}]", recorder.map.grant_count);
+            recorder.map.grant_count += 1;
+            recorder.record_fields(&key, grant.get_ref(), &["tables", "sequences"]);
+        }
+
+        Ok(())
+    }
+
+    fn locate(&self, key: &str) -> Option<SourceLocation> {
+        self.locations.get(key).cloned()
+    }
+}
+
+/// Writes the spans of one file into a SourceMap
+struct Recorder<'a> {
+    map: &'a mut SourceMap,
+    file: &'a Path,
+    text: &'a str,
+}
+
+impl Recorder<'_> {
+    fn record_table(&mut self, table: &DeTable<'_>) {
+        let Some(name) = table.get("name") else {
+            return;
+        };
+        let table_name = name.get_ref().as_str().unwrap_or_default().to_string();
+        let prefix = format!("table.{}", table_name);
+
+        // The last definition wins when the schema is built, so its
+        // locations replace the earlier ones
+        if let Some(previous) = self.map.locations.get(&prefix) {
+            let message = format!(
+                "Duplicate table `{}`, first defined at {}:{}",
+                table_name,
+                previous.file.display(),
+                previous.line
+            );
+            let location = self.location(name);
+            self.map.duplicates.push(Diagnostic::error(message, Some(location)));
+        }
+        self.record(&prefix, name);
+
+        // Shorthand columns expand ahead of the full `[[column]]` entries,
+        // and their `references` after the full `[[foreign_key]]` entries
+        let mut shorthand_count = 0;
+        let mut fk_count = table
+            .get("foreign_key")
+            .and_then(|fks| fks.get_ref().as_array())
+            .map_or(0, |fks| fks.len());
+        if let Some(DeValue::Table(shorthands)) = table.get("columns").map(|c| c.get_ref()) {
+            for (name, spec) in shorthands {
+                let key = format!("{}.column[{}]", prefix, shorthand_count);
+                self.record(&key, name);
+                self.record(&format!("{}.data_type", key), spec);
+                shorthand_count += 1;
+
+                let lowered = spec.get_ref().as_str().unwrap_or_default().to_lowercase();
+                if lowered.split_whitespace().any(|t| t == "references") {
+                    let key = format!("{}.foreign_key[{}]", prefix, fk_count);
+                    for field in ["", ".column", ".referenced_table", ".referenced_column"] {
+                        self.record(&format!("{}{}", key, field), spec);
+                    }
+                    fk_count += 1;
+                }
+            }
+        }
+
+        let fields: &[(&str, &[&str])] = &[
+            ("column", &["data_type"]),
+            ("foreign_key", &["column", "referenced_table", "referenced_column"]),
+            ("index", &["columns"]),
+        ];
+        for (array, keys) in fields {
+            let offset = if *array == "column" { shorthand_count } else { 0 };
+            if let Some(items) = table.get(*array) {
+                for (i, item) in items.get_ref().as_array().into_iter().flatten().enumerate() {
+                    let key = format!("{}.{}[{}]", prefix, array, i + offset);
+                    match item.get_ref().get("name") {
+                        Some(item_name) => self.record(&key, item_name),
+                        None => self.record(&key, item),
+                    }
+                    self.record_fields(&key, item.get_ref(), keys);
+                }
+            }
+        }
+
+        if let Some(partition_by) = table.get("partition_by") {
+            self.record_fields(&format!("{}.partition_by", prefix), partition_by.get_ref(), &["columns"]);
+        }
+    }
+
+    fn record_fields(&mut self, prefix: &str, value: &DeValue<'_>, keys: &[&str]) {
+        for key in keys {
+            if let Some(field) = value.get(*key) {
+                self.record(&format!("{}.{}", prefix, key), field);
+            }
+        }
+    }
+
+    fn record<T>(&mut self, key: &str, value: &Spanned<T>) {
+        let location = self.location(value);
+        self.map.locations.insert(key.to_string(), location);
+    }
+
+    fn location<T>(&self, value: &Spanned<T>) -> SourceLocation {
+        let offset = value.span().start.min(self.text.len());
+        let before = &self.text[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+
+        SourceLocation {
+            file: self.file.to_path_buf(),
+            line,
+            column,
+        }
+    }
+}
+
+fn array_items<'r, 'i>(table: &'r DeTable<'i>, key: &str) -> impl Iterator<Item = &'r Spanned<DeValue<'i>>> {
+    table
+        .get(key)
+        .and_then(|value| value.get_ref().as_array())
+        .into_iter()
+        .flatten()
+}